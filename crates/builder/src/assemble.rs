@@ -0,0 +1,310 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use flate2::{write::GzEncoder, Compression};
+
+use crate::{
+    diagnostics::Diagnostic,
+    feeds::feeds,
+    html::{inject_body, inject_meta, redirect_page, BASE_PATH_META},
+    site::Site,
+    sitemap::{ROBOTS, SITEMAP},
+    snapshots::snapshots,
+};
+
+/// The files that Trunk builds for the frontend.
+const FRONTEND: [&str; 2] = [
+    "avid-rustacean-frontend.js",
+    "avid-rustacean-frontend_bg.wasm",
+];
+
+/// The extensions of the files that are precompressed.
+const COMPRESSED: [&str; 6] = ["json", "js", "wasm", "xml", "ans", "txt"];
+
+/// Builds the frontend's crate with Trunk into the output directory. The frontend's files are at
+/// the root of the site, which is at `site_path` (e.g. `/blog`) when the base URL has a path.
+pub(crate) fn build_frontend(
+    frontend: &Path,
+    out: &Path,
+    site_path: &str,
+) -> Result<(), Diagnostic> {
+    let status = Command::new("trunk")
+        .current_dir(frontend)
+        .arg("build")
+        .arg("--release")
+        .arg("--dist")
+        .arg(out)
+        .arg("--public-url")
+        .arg(format!("{site_path}/"))
+        .arg("index.html")
+        .status()
+        .map_err(|err| Diagnostic::new(frontend, format!("could not run `trunk`: {err}")))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Diagnostic::new(frontend, "`trunk build` failed"))
+    }
+}
+
+/// Lays out the whole site in `dist` the way that it is deployed, using the frontend and JSON in
+/// `out`. Any existing contents of `dist` are removed first.
+///
+/// ```text
+/// dist/
+/// ├── CNAME
+/// ├── badge.json
+/// ├── sitemap-tui.xml, robots-tui.txt
+/// ├── avid-rustacean-frontend.js
+/// ├── avid-rustacean-frontend_bg.wasm
+/// └── tui/
+///     ├── index.html
+///     ├── site.json, home.json, projects.json, archive.json, search.json, aliases.json
+///     ├── code-theme.json
+///     ├── posts-1.json, posts-2.json, ...
+///     ├── atom.xml, feed.json
+///     ├── tags/<tag>/atom.xml, tags/<tag>/feed.json
+///     ├── projects/index.html
+///     ├── blog/index.html
+///     ├── archive/index.html
+///     ├── blog/<post>/index.html
+///     ├── blog/<alias>/index.html
+///     ├── blog/<post>.ans, blog/<post>.txt
+///     └── posts/<post>.json
+/// ```
+///
+/// Each route of the frontend gets a copy of `index.html` so that static hosts can serve any route
+/// directly. The copies are marked with the path that the TUI is served under, and hold a
+/// prerendered snapshot of the route's page (see [`snapshots`]). The old routes of
+/// posts (their aliases) get pages that redirect to the posts. Each post can also be read in a
+/// terminal as text (see [`crate::text`]). The JSON, JS, WASM, feed, and text files are also
+/// precompressed with gzip and brotli.
+pub(crate) fn assemble(site: &Site, out: &Path, dist: &Path) -> Result<(), Diagnostic> {
+    if dist.exists() {
+        std::fs::remove_dir_all(dist).map_err(io_err(dist, "remove the old site"))?;
+    }
+
+    let mut files: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut copy = |from: PathBuf, to: PathBuf| files.push((from, to));
+    // The custom domain is at the root of the Zola site, next to its content
+    let cname = site.content.with_file_name("CNAME");
+    if cname.exists() {
+        copy(cname, dist.join("CNAME"));
+    }
+    for name in ["badge.json", SITEMAP, ROBOTS] {
+        copy(out.join(name), dist.join(name));
+    }
+    for name in FRONTEND {
+        copy(out.join(name), dist.join(name));
+    }
+    for name in [
+        "site.json",
+        "home.json",
+        "projects.json",
+        "archive.json",
+        "search.json",
+        "aliases.json",
+        "code-theme.json",
+    ] {
+        let from = out.join(name);
+        // The code theme is optional
+        if name != "code-theme.json" || from.exists() {
+            copy(from, dist.join("tui").join(name));
+        }
+    }
+    for page in site.post_pages() {
+        let name = format!("posts-{}.json", page.page);
+        copy(out.join(&name), dist.join("tui").join(name));
+    }
+    for post in &site.posts {
+        let name = format!("posts/{}.json", post.summary.real_name);
+        copy(out.join(&name), dist.join("tui").join(name));
+        for ext in ["ans", "txt"] {
+            let name = format!("{}.{ext}", post.summary.real_name);
+            copy(
+                out.join("text").join(&name),
+                dist.join("tui/blog").join(name),
+            );
+        }
+    }
+    for (name, _) in feeds(site) {
+        copy(out.join("feeds").join(&name), dist.join("tui").join(name));
+    }
+
+    let index = out.join("index.html");
+    let html = std::fs::read_to_string(&index).map_err(io_err(&index, "read the frontend"))?;
+    let html = inject_meta(&html, BASE_PATH_META, &site.config.base_path());
+    let theme = out.join("code-theme.json");
+    let theme = std::fs::read_to_string(&theme)
+        .ok()
+        .map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(|err| Diagnostic::new(&theme, format!("invalid code theme: {err}")))?;
+    for (route, snapshot) in snapshots(site, theme) {
+        let to = dist.join(route).join("index.html");
+        std::fs::create_dir_all(to.parent().unwrap())
+            .and_then(|_| std::fs::write(&to, inject_body(&html, &snapshot)))
+            .map_err(io_err(&to, "write the file"))?;
+    }
+    for (route, url) in site.redirects() {
+        let to = dist.join(route).join("index.html");
+        // On a case-insensitive file system, an old spelling of a post's name is the post's own
+        // page, which has to be kept
+        if to.exists() {
+            continue;
+        }
+        std::fs::create_dir_all(to.parent().unwrap())
+            .and_then(|_| std::fs::write(&to, redirect_page(&url)))
+            .map_err(io_err(&to, "write the file"))?;
+    }
+
+    for (from, to) in files {
+        std::fs::create_dir_all(to.parent().unwrap())
+            .and_then(|_| std::fs::copy(&from, &to))
+            .map_err(io_err(&from, "copy the file into the site"))?;
+        if to
+            .extension()
+            .is_some_and(|ext| COMPRESSED.iter().any(|c| ext == *c))
+        {
+            let data = std::fs::read(&to).map_err(io_err(&to, "read the file"))?;
+            let gz = with_extension(&to, "gz");
+            std::fs::write(&gz, gzip(&data)).map_err(io_err(&gz, "write the file"))?;
+            let br = with_extension(&to, "br");
+            std::fs::write(&br, brotli(&data)).map_err(io_err(&br, "write the file"))?;
+        }
+    }
+    Ok(())
+}
+
+fn io_err<'a>(path: &'a Path, what: &'a str) -> impl FnOnce(std::io::Error) -> Diagnostic + 'a {
+    move |err| Diagnostic::new(path, format!("could not {what}: {err}"))
+}
+
+/// Appends an extension, e.g. `app.wasm` becomes `app.wasm.gz`.
+fn with_extension(path: &Path, ext: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(ext);
+    path.into()
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn brotli(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    {
+        let mut encoder = brotli::CompressorWriter::new(&mut output, 4096, 11, 22);
+        encoder.write_all(data).unwrap();
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Read, path::Path};
+
+    use crate::{cache::BuildCache, feeds::feeds, site::Site, testing::repo, text::renditions};
+
+    use super::assemble;
+
+    #[test]
+    fn site_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let (out, dist) = (dir.path().join("out"), dir.path().join("dist"));
+        let mut site = Site::load(
+            &repo().join("content"),
+            &repo().join("config.toml"),
+            &mut BuildCache::default(),
+        )
+        .unwrap();
+        site.posts[0].summary.aliases.push("old-name".to_owned());
+        std::fs::create_dir_all(&out).unwrap();
+        std::fs::write(
+            out.join("index.html"),
+            "<html><head></head><body></body></html>",
+        )
+        .unwrap();
+        for name in [
+            "badge.json",
+            "sitemap-tui.xml",
+            "robots-tui.txt",
+            "avid-rustacean-frontend.js",
+            "avid-rustacean-frontend_bg.wasm",
+        ] {
+            std::fs::write(out.join(name), name).unwrap();
+        }
+        std::fs::create_dir_all(out.join("posts")).unwrap();
+        for (name, json) in site.json() {
+            std::fs::write(out.join(name), json).unwrap();
+        }
+        std::fs::create_dir_all(out.join("feeds")).unwrap();
+        for (name, feed) in feeds(&site) {
+            let path = out.join("feeds").join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, feed).unwrap();
+        }
+        std::fs::create_dir_all(out.join("text")).unwrap();
+        for (name, text) in renditions(&site, None) {
+            std::fs::write(out.join("text").join(name), text).unwrap();
+        }
+        assemble(&site, &out, &dist).unwrap();
+
+        let exists = |path: &str| assert!(dist.join(path).is_file(), "{path} is missing");
+        exists("CNAME");
+        exists("badge.json");
+        exists("sitemap-tui.xml");
+        exists("avid-rustacean-frontend_bg.wasm");
+        exists("tui/index.html");
+        let index = std::fs::read_to_string(dist.join("tui/index.html")).unwrap();
+        assert!(index.starts_with(
+            r#"<html><head><meta name="tui-base" content="/tui"></head><body><pre id="tui-snapshot""#
+        ));
+        assert!(index.ends_with("</noscript></body></html>"));
+        exists("tui/site.json");
+        exists("tui/projects/index.html");
+        exists("tui/blog/index.html");
+        exists("tui/archive/index.html");
+        exists("tui/archive.json");
+        exists("tui/posts-1.json");
+        exists("tui/search.json");
+        exists("tui/atom.xml");
+        exists("tui/feed.json");
+        for post in &site.posts {
+            let name = &post.summary.real_name;
+            exists(&format!("tui/blog/{name}/index.html"));
+            exists(&format!("tui/posts/{name}.json"));
+            exists(&format!("tui/blog/{name}.ans"));
+            exists(&format!("tui/blog/{name}.txt"));
+        }
+        assert!(!dist.join("tui/code-theme.json").exists());
+        exists("tui/aliases.json");
+        let redirect = std::fs::read_to_string(dist.join("tui/blog/old-name/index.html")).unwrap();
+        assert!(redirect.contains(&format!(
+            r#"url=https://avid-rustacean.dev/tui/blog/{}""#,
+            site.posts[0].summary.real_name
+        )));
+
+        // The compressed files are real gzip and brotli streams of the originals
+        let wasm = Path::new("avid-rustacean-frontend_bg.wasm");
+        let read = |ext: &str| std::fs::read(dist.join(wasm).with_extension(ext)).unwrap();
+        let mut gz = String::new();
+        flate2::read::GzDecoder::new(&read("wasm.gz")[..])
+            .read_to_string(&mut gz)
+            .unwrap();
+        assert_eq!(gz, "avid-rustacean-frontend_bg.wasm");
+        let mut br = String::new();
+        brotli::Decompressor::new(&read("wasm.br")[..], 4096)
+            .read_to_string(&mut br)
+            .unwrap();
+        assert_eq!(br, "avid-rustacean-frontend_bg.wasm");
+        exists("tui/posts-1.json.gz");
+        exists("tui/posts-1.json.br");
+        assert!(!dist.join("tui/index.html.gz").exists());
+    }
+}
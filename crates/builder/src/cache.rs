@@ -0,0 +1,177 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::UNIX_EPOCH,
+};
+
+use avid_rustacean_model::{Markdown, Post};
+use serde::{Deserialize, Serialize};
+
+/// A cache of parsed pages, keyed by the hash of each source file and the version of the builder.
+/// Parsing (and especially highlighting code) is the slowest part of a build, so unchanged files
+/// are taken from the cache instead.
+#[derive(Debug, Default)]
+pub(crate) struct BuildCache {
+    /// The entries from the last run.
+    old: Entries,
+    /// The entries that have been used or added during this run. Only these are saved, so files
+    /// that no longer exist fall out of the cache.
+    new: Entries,
+    hits: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Entries {
+    pages: HashMap<PathBuf, CacheEntry<Markdown>>,
+    posts: HashMap<PathBuf, CacheEntry<Post>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry<T> {
+    hash: String,
+    value: T,
+}
+
+impl BuildCache {
+    /// Loads the cache. A missing or unreadable cache is treated as empty.
+    pub(crate) fn load(path: &Path) -> Self {
+        let old = std::fs::read(path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        Self {
+            old,
+            ..Self::default()
+        }
+    }
+
+    /// Saves the cache. Failing to save the cache only makes the next build slower, so any errors
+    /// are ignored.
+    pub(crate) fn save(&self, path: &Path) {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let _ = std::fs::write(path, serde_json::to_string(&self.new).unwrap());
+    }
+
+    /// The number of files that were taken from the cache.
+    pub(crate) fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Gets a page from the cache, or parses and caches it.
+    pub(crate) fn page<E>(
+        &mut self,
+        path: &Path,
+        source: &str,
+        parse: impl FnOnce() -> Result<Markdown, E>,
+    ) -> Result<Markdown, E> {
+        lookup(
+            &mut self.old.pages,
+            &mut self.new.pages,
+            &mut self.hits,
+            path,
+            source,
+            parse,
+        )
+    }
+
+    /// Gets a post from the cache, or parses and caches it.
+    pub(crate) fn post<E>(
+        &mut self,
+        path: &Path,
+        source: &str,
+        parse: impl FnOnce() -> Result<Post, E>,
+    ) -> Result<Post, E> {
+        lookup(
+            &mut self.old.posts,
+            &mut self.new.posts,
+            &mut self.hits,
+            path,
+            source,
+            parse,
+        )
+    }
+}
+
+fn lookup<T: Clone, E>(
+    old: &mut HashMap<PathBuf, CacheEntry<T>>,
+    new: &mut HashMap<PathBuf, CacheEntry<T>>,
+    hits: &mut usize,
+    path: &Path,
+    source: &str,
+    parse: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    let hash = hash(source);
+    let entry = match old.remove(path).filter(|entry| entry.hash == hash) {
+        Some(entry) => {
+            *hits += 1;
+            entry
+        }
+        None => CacheEntry {
+            hash,
+            value: parse()?,
+        },
+    };
+    let value = entry.value.clone();
+    new.insert(path.to_owned(), entry);
+    Ok(value)
+}
+
+/// Hashes a source file along with the version of the builder, so that a new version of the
+/// builder never uses what an older version parsed.
+fn hash(source: &str) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(builder_version().as_bytes());
+    hasher.update(&[0]);
+    hasher.update(source.as_bytes());
+    hasher.finalize().to_hex().to_string()
+}
+
+/// The version of the builder. Besides the crate's version, this includes when the builder was
+/// compiled so that changes to the builder during development also invalidate the cache.
+fn builder_version() -> &'static str {
+    static VERSION: OnceLock<String> = OnceLock::new();
+    VERSION.get_or_init(|| {
+        let compiled = std::env::current_exe()
+            .and_then(std::fs::metadata)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |time| time.as_nanos());
+        format!("{} {compiled}", env!("CARGO_PKG_VERSION"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use avid_rustacean_model::Markdown;
+
+    use super::BuildCache;
+
+    #[test]
+    fn unchanged_files_are_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+        let parse = |src: &str| src.parse::<Markdown>();
+
+        let mut cache = BuildCache::load(&path);
+        cache.page(Path::new("a.md"), "A", || parse("A")).unwrap();
+        cache.page(Path::new("b.md"), "B", || parse("B")).unwrap();
+        assert_eq!(cache.hits(), 0);
+        cache.save(&path);
+
+        let mut cache = BuildCache::load(&path);
+        let a = cache
+            .page(Path::new("a.md"), "A", || -> Result<_, ()> {
+                panic!("a.md should be cached")
+            })
+            .unwrap();
+        assert_eq!(a, parse("A").unwrap());
+        cache.page(Path::new("b.md"), "B2", || parse("B2")).unwrap();
+        assert_eq!(cache.hits(), 1);
+    }
+}
@@ -0,0 +1,114 @@
+use std::path::Path;
+
+use avid_rustacean_model::{origin, MenuItem, SiteInfo};
+use serde::Deserialize;
+
+use crate::diagnostics::Diagnostic;
+
+/// The parts of Zola's `config.toml` that the builder cares about.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub(crate) struct SiteConfig {
+    /// The URL the site will be built for, e.g. `https://avid-rustacean.dev`.
+    pub(crate) base_url: String,
+    /// The title of the site.
+    pub(crate) title: String,
+    /// The author of every post.
+    pub(crate) author: Option<String>,
+    #[serde(default)]
+    pub(crate) extra: Extra,
+}
+
+/// The parts of the `[extra]` table that the builder cares about.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+pub(crate) struct Extra {
+    /// The GUI's menu. The links that leave the site are shown in the TUI's footer.
+    #[serde(default)]
+    pub(crate) menu_items: Vec<MenuItem>,
+    /// Links that are only shown in the TUI's footer, after those from the menu.
+    #[serde(default)]
+    pub(crate) tui_links: Vec<MenuItem>,
+}
+
+impl SiteConfig {
+    /// Loads the config from a Zola `config.toml`.
+    pub(crate) fn load(path: &Path) -> Result<Self, Diagnostic> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|err| Diagnostic::new(path, format!("could not read the config: {err}")))?;
+        toml::from_str(&data).map_err(|err| Diagnostic::from_toml(path, &data, &data, 1, &err))
+    }
+
+    /// The scheme and host of the base URL, e.g. `https://avid-rustacean.dev`.
+    pub(crate) fn origin(&self) -> &str {
+        origin(&self.base_url)
+    }
+
+    /// The path of the base URL without a trailing slash. This is empty when the site is at the
+    /// root of its domain.
+    pub(crate) fn site_path(&self) -> &str {
+        self.base_url[self.origin().len()..].trim_end_matches('/')
+    }
+
+    /// The path that the TUI is served under, e.g. `/tui`.
+    pub(crate) fn base_path(&self) -> String {
+        format!("{}/tui", self.site_path())
+    }
+
+    /// Everything that the frontend needs to know about the site.
+    pub(crate) fn site_info(&self) -> SiteInfo {
+        let base_url = self.base_url.trim_end_matches('/');
+        // Zola fills in `$BASE_URL`, and those links are to the GUI
+        let menu = self
+            .extra
+            .menu_items
+            .iter()
+            .filter(|item| !item.url.starts_with("$BASE_URL") && !item.url.starts_with(base_url))
+            .cloned();
+        SiteInfo {
+            title: self.title.clone(),
+            base_url: base_url.to_owned(),
+            base_path: self.base_path(),
+            author: self.author.clone(),
+            links: menu.chain(self.extra.tui_links.iter().cloned()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::SiteConfig;
+
+    #[test]
+    fn load_repo_config() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../config.toml");
+        let config = SiteConfig::load(&path).unwrap();
+        assert_eq!(config.base_url, "https://avid-rustacean.dev");
+        assert_eq!(config.title, "The Avid Rustacean");
+        assert_eq!(config.author.as_deref(), Some("Tyler Bloom"));
+        assert_eq!(config.base_path(), "/tui");
+
+        let info = config.site_info();
+        let links: Vec<_> = info.links.iter().map(|link| link.name.as_str()).collect();
+        assert_eq!(links, ["GitHub", "LinkedIn", "Email", "Repo"]);
+    }
+
+    #[test]
+    fn base_url_with_a_path() {
+        let config: SiteConfig = toml::from_str(
+            r#"
+            base_url = "https://example.github.io/blog/"
+            title = "Example"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.origin(), "https://example.github.io");
+        assert_eq!(config.site_path(), "/blog");
+        assert_eq!(config.base_path(), "/blog/tui");
+        assert_eq!(
+            config.site_info().base_url,
+            "https://example.github.io/blog"
+        );
+        assert!(config.site_info().links.is_empty());
+    }
+}
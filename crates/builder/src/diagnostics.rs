@@ -0,0 +1,143 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+/// A problem with the site's content or config that stops it from being built. Each diagnostic
+/// points at the file that caused it and, when known, the line and front matter field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Diagnostic {
+    pub(crate) file: PathBuf,
+    /// The one-indexed line in the file and the text of that line.
+    pub(crate) line: Option<(usize, String)>,
+    /// The TOML field that the problem is with.
+    pub(crate) field: Option<String>,
+    pub(crate) message: String,
+}
+
+impl Diagnostic {
+    pub(crate) fn new(file: &Path, message: impl Into<String>) -> Self {
+        Self {
+            file: file.to_owned(),
+            line: None,
+            field: None,
+            message: message.into(),
+        }
+    }
+
+    /// Points the diagnostic at a (one-indexed) line of the file's source.
+    pub(crate) fn at_line(mut self, source: &str, line: usize) -> Self {
+        let text = source
+            .lines()
+            .nth(line.saturating_sub(1))
+            .unwrap_or_default();
+        self.line = Some((line, text.to_owned()));
+        self
+    }
+
+    pub(crate) fn in_field(mut self, field: impl Into<String>) -> Self {
+        self.field = Some(field.into());
+        self
+    }
+
+    /// Creates a diagnostic from an error in some TOML. The TOML might be embedded in a larger
+    /// file (like front matter), so `first_line` is the line of the file that the TOML starts on.
+    pub(crate) fn from_toml(
+        file: &Path,
+        source: &str,
+        toml: &str,
+        first_line: usize,
+        err: &toml::de::Error,
+    ) -> Self {
+        let digest = Self::new(file, err.message());
+        let Some(span) = err.span() else {
+            return digest;
+        };
+        let line = first_line + toml[..span.start].matches('\n').count();
+        let digest = digest.at_line(source, line);
+        // TOML errors point at a value, so the field is the key on that line (if there is one)
+        let field = digest
+            .line
+            .as_ref()
+            .and_then(|(_, text)| text.split_once('='))
+            .map(|(key, _)| key.trim())
+            .filter(|key| {
+                !key.is_empty()
+                    && key
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+            })
+            .map(ToOwned::to_owned);
+        match field {
+            Some(field) => digest.in_field(field),
+            None => digest,
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "error: {}", self.message)?;
+        match &self.line {
+            Some((line, text)) => {
+                let width = line.to_string().len();
+                let pad = " ".repeat(width);
+                writeln!(f, "{pad}--> {}:{line}", self.file.display())?;
+                writeln!(f, "{pad} |")?;
+                writeln!(f, "{line} | {text}")?;
+                writeln!(f, "{pad} |")?;
+                if let Some(field) = &self.field {
+                    writeln!(f, "{pad} = in the `{field}` field")?;
+                }
+            }
+            None => {
+                writeln!(f, " --> {}", self.file.display())?;
+                if let Some(field) = &self.field {
+                    writeln!(f, "  = in the `{field}` field")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Prints every diagnostic and then exits with a non-zero status.
+pub(crate) fn exit_with(diagnostics: Vec<Diagnostic>) -> ! {
+    for diagnostic in &diagnostics {
+        eprintln!("{diagnostic}");
+    }
+    let plural = if diagnostics.len() == 1 { "" } else { "s" };
+    eprintln!(
+        "error: could not build the site due to {} error{plural}",
+        diagnostics.len()
+    );
+    std::process::exit(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::Diagnostic;
+
+    #[test]
+    fn snippet_output() {
+        let source = "+++\ntitle = \"Hi\"\ndate = 12 13\n+++\nBody";
+        let toml = "title = \"Hi\"\ndate = 12 13";
+        let err = toml::from_str::<toml::Table>(toml).unwrap_err();
+        let diagnostic = Diagnostic::from_toml(Path::new("post.md"), source, toml, 2, &err);
+        assert_eq!(diagnostic.line.as_ref().unwrap().0, 3);
+        assert_eq!(diagnostic.field.as_deref(), Some("date"));
+        let output = diagnostic.to_string();
+        assert!(output.starts_with("error: "));
+        assert!(output.contains(" --> post.md:3\n"));
+        assert!(output.contains("3 | date = 12 13\n"));
+        assert!(output.contains("= in the `date` field"));
+
+        let diagnostic = Diagnostic::new(Path::new("post.md"), "oops").in_field("title");
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: oops\n --> post.md\n  = in the `title` field\n"
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::BTreeMap;
+
+use avid_rustacean_model::{slugify, Markdown, MdNode, Post};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde_json::json;
+
+use crate::{
+    html::{escape, render},
+    site::{updated, Site},
+};
+
+/// A feed of posts, newest first.
+struct Feed<'a> {
+    title: String,
+    /// The path of the feed's files, relative to `/tui`, e.g. `tags/rust/`.
+    dir: String,
+    posts: Vec<&'a Post>,
+}
+
+/// Generates the Atom feed (`atom.xml`) and JSON Feed (`feed.json`) of every post, as well as the
+/// same pair of feeds for each tag (under `tags/<tag>/`). Each entry has the full content of the
+/// post rendered as HTML. The returned paths are relative to `/tui`.
+pub(crate) fn feeds(site: &Site) -> Vec<(String, String)> {
+    let newest_first = || site.posts.iter().rev();
+    let mut tags: BTreeMap<String, Feed<'_>> = BTreeMap::new();
+    for post in newest_first() {
+        for tag in &post.summary.tags {
+            tags.entry(slugify(tag))
+                .or_insert_with(|| Feed {
+                    title: format!("{} - {tag}", site.config.title),
+                    dir: format!("tags/{}/", slugify(tag)),
+                    posts: Vec::new(),
+                })
+                .posts
+                .push(post);
+        }
+    }
+    let all = Feed {
+        title: site.config.title.clone(),
+        dir: String::new(),
+        posts: newest_first().collect(),
+    };
+
+    std::iter::once(all)
+        .chain(tags.into_values())
+        .flat_map(|feed| {
+            [
+                (format!("{}atom.xml", feed.dir), atom(site, &feed)),
+                (format!("{}feed.json", feed.dir), json_feed(site, &feed)),
+            ]
+        })
+        .collect()
+}
+
+fn tui_url(site: &Site, path: &str) -> String {
+    format!("{}/tui/{path}", site.config.base_url.trim_end_matches('/'))
+}
+
+fn post_url(site: &Site, post: &Post) -> String {
+    tui_url(site, &format!("blog/{}", post.summary.real_name))
+}
+
+fn author(site: &Site) -> &str {
+    site.config.author.as_deref().unwrap_or(&site.config.title)
+}
+
+/// Turns the date of a post into an RFC 3339 timestamp. Dates without a time are taken to be at
+/// midnight UTC.
+pub(crate) fn timestamp(date: &str) -> String {
+    if let Ok(date) = DateTime::parse_from_rfc3339(date) {
+        date.to_rfc3339()
+    } else if let Ok(date) = NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S") {
+        date.and_utc().to_rfc3339()
+    } else if let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        date.and_hms_opt(0, 0, 0).unwrap().and_utc().to_rfc3339()
+    } else {
+        date.to_owned()
+    }
+}
+
+fn atom(site: &Site, feed: &Feed<'_>) -> String {
+    let url = tui_url(site, &format!("{}atom.xml", feed.dir));
+    let last_updated = feed
+        .posts
+        .iter()
+        .map(|post| updated(post))
+        .max()
+        .unwrap_or_else(|| timestamp("1970-01-01"));
+    let mut xml = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>{title}</title>
+  <link href="{url}" rel="self" type="application/atom+xml"/>
+  <link href="{home}" rel="alternate" type="text/html"/>
+  <id>{url}</id>
+  <updated>{last_updated}</updated>
+  <author><name>{author}</name></author>
+"#,
+        title = escape(&feed.title),
+        url = escape(&url),
+        home = escape(&tui_url(site, "")),
+        author = escape(author(site)),
+    );
+    for post in &feed.posts {
+        let url = post_url(site, post);
+        xml.push_str(&format!(
+            r#"  <entry>
+    <title>{title}</title>
+    <link href="{url}" rel="alternate" type="text/html"/>
+    <id>{url}</id>
+    <published>{published}</published>
+    <updated>{updated}</updated>
+"#,
+            title = escape(&post.summary.title),
+            url = escape(&url),
+            published = timestamp(&post.summary.create_on),
+            updated = updated(post),
+        ));
+        for tag in &post.summary.tags {
+            xml.push_str(&format!("    <category term=\"{}\"/>\n", escape(tag)));
+        }
+        xml.push_str(&format!(
+            "    <summary type=\"html\">{}</summary>\n    <content type=\"html\">{}</content>\n  </entry>\n",
+            escape(&render(&post.summary.summary, &site.config.base_url)),
+            escape(&render(&post.body, &site.config.base_url)),
+        ));
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+/// The text of some markdown without any of its markup, for JSON Feed's `summary` (which is plain
+/// text, unlike its content).
+fn plain_text(md: &Markdown) -> String {
+    fn inner(node: &MdNode) -> String {
+        match node {
+            MdNode::Paragraph(nodes) => nodes.iter().map(inner).collect(),
+            MdNode::List(items) => items.iter().map(inner).collect::<Vec<_>>().join("\n"),
+            MdNode::Code(code) => code.source.clone(),
+            MdNode::BlockQuote(text)
+            | MdNode::InlineCode(text)
+            | MdNode::Emphasis(text)
+            | MdNode::Link(text, _)
+            | MdNode::Strong(text)
+            | MdNode::Heading(text)
+            | MdNode::Text(text) => text.clone(),
+            MdNode::Break => "\n".to_owned(),
+            MdNode::ThematicBreak => String::new(),
+        }
+    }
+    md.0.iter()
+        .map(inner)
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn json_feed(site: &Site, feed: &Feed<'_>) -> String {
+    let items: Vec<_> = feed
+        .posts
+        .iter()
+        .map(|post| {
+            let url = post_url(site, post);
+            json!({
+                "id": url,
+                "url": url,
+                "title": post.summary.title,
+                "content_html": render(&post.body, &site.config.base_url),
+                "summary": plain_text(&post.summary.summary),
+                "date_published": timestamp(&post.summary.create_on),
+                "date_modified": updated(post),
+                "tags": post.summary.tags,
+            })
+        })
+        .collect();
+    let feed = json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": feed.title,
+        "home_page_url": tui_url(site, ""),
+        "feed_url": tui_url(site, &format!("{}feed.json", feed.dir)),
+        "authors": [{ "name": author(site) }],
+        "items": items,
+    });
+    serde_json::to_string_pretty(&feed).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{site::Site, testing};
+
+    use super::{feeds, timestamp};
+
+    fn site() -> Site {
+        let mut site = testing::site();
+        site.config.author = Some("Someone".to_owned());
+        for post in &mut site.posts {
+            // The titles have to be escaped
+            post.summary.title = format!("The <{}> post", post.summary.real_name);
+            post.summary.summary = "A *short* [summary](/tui/blog/other)".parse().unwrap();
+            post.body = "Some [link](/tui/blog/other) & more".parse().unwrap();
+        }
+        site
+    }
+
+    #[test]
+    fn dates_become_timestamps() {
+        assert_eq!(timestamp("2024-02-28"), "2024-02-28T00:00:00+00:00");
+        assert_eq!(
+            timestamp("2024-02-28T10:30:00"),
+            "2024-02-28T10:30:00+00:00"
+        );
+        assert_eq!(
+            timestamp("2024-02-28T10:30:00-05:00"),
+            "2024-02-28T10:30:00-05:00"
+        );
+    }
+
+    #[test]
+    fn atom_and_json_feeds() {
+        let feeds: HashMap<_, _> = feeds(&site()).into_iter().collect();
+        let mut names: Vec<_> = feeds.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "atom.xml",
+                "feed.json",
+                "tags/rust/atom.xml",
+                "tags/rust/feed.json",
+                "tags/web-dev/atom.xml",
+                "tags/web-dev/feed.json",
+            ]
+        );
+
+        let atom = roxmltree::Document::parse(&feeds["atom.xml"]).unwrap();
+        let root = atom.root_element();
+        assert_eq!(
+            root.tag_name().namespace(),
+            Some("http://www.w3.org/2005/Atom")
+        );
+        let entries: Vec<_> = root
+            .children()
+            .filter(|node| node.has_tag_name("entry"))
+            .collect();
+        assert_eq!(entries.len(), 2);
+        let child = |node: roxmltree::Node<'_, '_>, name: &str| {
+            node.children()
+                .find(|child| child.has_tag_name(name))
+                .and_then(|child| child.text())
+                .unwrap()
+                .to_owned()
+        };
+        assert_eq!(child(entries[0], "title"), "The <new> post");
+        assert_eq!(child(entries[0], "id"), "https://example.com/tui/blog/new");
+        assert_eq!(
+            child(entries[0], "content"),
+            "<p>Some <a href=\"https://example.com/tui/blog/other\">link</a> &amp; more</p>\n"
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&feeds["tags/web-dev/feed.json"]).unwrap();
+        assert_eq!(json["version"], "https://jsonfeed.org/version/1.1");
+        assert_eq!(json["title"], "Example - Web Dev");
+        assert_eq!(
+            json["feed_url"],
+            "https://example.com/tui/tags/web-dev/feed.json"
+        );
+        let items = json["items"].as_array().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["url"], "https://example.com/tui/blog/new");
+        assert_eq!(items[0]["date_published"], "2024-02-28T00:00:00+00:00");
+        assert_eq!(items[0]["summary"], "A short summary");
+        assert_eq!(items[0]["tags"], serde_json::json!(["Rust", "Web Dev"]));
+    }
+}
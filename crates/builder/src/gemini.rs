@@ -0,0 +1,186 @@
+use std::{
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use rustls::{
+    crypto::ring::default_provider,
+    pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer},
+    ServerConfig,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+use tokio_rustls::TlsAcceptor;
+
+use crate::{build, diagnostics::Diagnostic, Cli, GeminiOptions};
+
+/// The longest request that Gemini allows: a URL of up to 1024 bytes and a CRLF.
+const MAX_REQUEST: u64 = 1026;
+
+/// Builds the site and serves its capsule on localhost, to preview it in a Gemini client. Gemini is
+/// always over TLS, so a self-signed certificate for `localhost` is made whenever the server
+/// starts. Clients trust certificates on first use, so they will say that it changed since the
+/// last time.
+pub(crate) fn serve(cli: &Cli, options: &GeminiOptions) -> Result<(), Vec<Diagnostic>> {
+    build(cli, &options.build)?;
+    let root = cli.out.join("gemini");
+    let acceptor = acceptor().map_err(|err| {
+        vec![Diagnostic::new(
+            &root,
+            format!("could not make a certificate: {err}"),
+        )]
+    })?;
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let addr = (Ipv4Addr::LOCALHOST, options.port);
+    let listener = runtime.block_on(TcpListener::bind(addr)).map_err(|err| {
+        vec![Diagnostic::new(
+            &root,
+            format!("could not listen on port {}: {err}", options.port),
+        )]
+    })?;
+    println!(
+        "Serving the capsule at gemini://localhost:{}/",
+        options.port
+    );
+    runtime.block_on(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                continue;
+            };
+            let (acceptor, root) = (acceptor.clone(), root.clone());
+            tokio::spawn(async move {
+                if let Err(err) = respond(&acceptor, stream, &root).await {
+                    eprintln!("could not respond to a request: {err}");
+                }
+            });
+        }
+    })
+}
+
+fn acceptor() -> Result<TlsAcceptor, Box<dyn std::error::Error>> {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()])?;
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(cert.signing_key.serialize_der()));
+    let config = ServerConfig::builder_with_provider(Arc::new(default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.cert.der().clone()], key)?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+async fn respond(acceptor: &TlsAcceptor, stream: TcpStream, root: &Path) -> std::io::Result<()> {
+    let mut stream = acceptor.accept(stream).await?;
+    let mut request = Vec::new();
+    BufReader::new((&mut stream).take(MAX_REQUEST))
+        .read_until(b'\n', &mut request)
+        .await?;
+    let (header, body) = match std::str::from_utf8(&request) {
+        Ok(request) if request.ends_with("\r\n") => response(root, request.trim_end()),
+        _ => (
+            "59 The request is not a URL followed by CRLF".to_owned(),
+            None,
+        ),
+    };
+    stream.write_all(format!("{header}\r\n").as_bytes()).await?;
+    if let Some(body) = body {
+        stream.write_all(&body).await?;
+    }
+    stream.shutdown().await
+}
+
+/// The header of the response to a request for a URL, and the body if it is a success.
+fn response(root: &Path, url: &str) -> (String, Option<Vec<u8>>) {
+    let Some(rest) = url.strip_prefix("gemini://") else {
+        return ("53 Only Gemini is served here".to_owned(), None);
+    };
+    // The host is whatever the client connected to
+    let path = rest.find('/').map_or("/", |i| &rest[i..]);
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let not_found = ("51 Not found".to_owned(), None);
+    if path
+        .split('/')
+        .any(|segment| segment == ".." || segment.contains('\\'))
+    {
+        return not_found;
+    }
+    let file = match resolve(root, path) {
+        Resolved::File(file) => file,
+        Resolved::Redirect(to) => return (format!("31 {to}"), None),
+        Resolved::NotFound => return not_found,
+    };
+    match std::fs::read(&file) {
+        Ok(body) => {
+            let mime = match file.extension().and_then(|ext| ext.to_str()) {
+                Some("gmi") => "text/gemini; charset=utf-8",
+                _ => "text/plain; charset=utf-8",
+            };
+            (format!("20 {mime}"), Some(body))
+        }
+        Err(_) => not_found,
+    }
+}
+
+/// What is served at a path of the capsule.
+#[derive(Debug, PartialEq)]
+enum Resolved {
+    File(PathBuf),
+    Redirect(String),
+    NotFound,
+}
+
+/// Directories are served by their `index.gmi`, and are redirected to with a trailing slash so
+/// that relative links work.
+fn resolve(root: &Path, path: &str) -> Resolved {
+    let relative = path.trim_start_matches('/');
+    let file = root.join(relative);
+    if path.ends_with('/') || relative.is_empty() {
+        return Resolved::File(file.join("index.gmi"));
+    }
+    if file.is_dir() {
+        return Resolved::Redirect(format!("{path}/"));
+    }
+    match file.is_file() {
+        true => Resolved::File(file),
+        false => Resolved::NotFound,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::response;
+    use crate::testing::files;
+
+    #[test]
+    fn requests_are_answered() {
+        let dir = files([
+            ("index.gmi", "# Home\n"),
+            ("blog/index.gmi", "# Blog\n"),
+            ("blog/post.gmi", "# Post\n"),
+        ]);
+        let root = dir.path();
+
+        let body = |url: &str| {
+            let (header, body) = response(root, url);
+            assert_eq!(header, "20 text/gemini; charset=utf-8");
+            String::from_utf8(body.unwrap()).unwrap()
+        };
+        assert_eq!(body("gemini://localhost"), "# Home\n");
+        assert_eq!(body("gemini://localhost:1965/"), "# Home\n");
+        assert_eq!(body("gemini://localhost/blog/"), "# Blog\n");
+        assert_eq!(body("gemini://localhost/blog/post.gmi?query"), "# Post\n");
+
+        let header = |url: &str| response(root, url).0;
+        assert_eq!(header("gemini://localhost/blog"), "31 /blog/");
+        assert_eq!(
+            header("gemini://localhost/blog/missing.gmi"),
+            "51 Not found"
+        );
+        assert_eq!(header("gemini://localhost/../secrets"), "51 Not found");
+        assert_eq!(
+            header("https://localhost/"),
+            "53 Only Gemini is served here"
+        );
+    }
+}
@@ -0,0 +1,404 @@
+use std::collections::BTreeMap;
+
+use avid_rustacean_model::{slugify, Markdown, MdNode, PostSummary};
+
+use crate::site::Site;
+
+/// A line of a page for the small web. Gemtext is made of lines like these, and gophermaps are
+/// close enough that they are made from the same ones (see [`crate::gopher`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Line {
+    Text(String),
+    /// A heading and its level, from 1 to 3.
+    Heading(u8, String),
+    Item(String),
+    Quote(String),
+    Link(Target, String),
+    /// The lines of a code block, and its language.
+    Preformatted(Option<String>, Vec<String>),
+    Blank,
+}
+
+/// Where a link leads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Target {
+    Page(Page),
+    /// An absolute URL that leaves the capsule (or the gopher hole).
+    Url(String),
+}
+
+/// The pages that are made for the small web. These mirror the TUI's routes, except that the
+/// archive is the blog index (which has every post) and each tag has an index of its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Page {
+    Home,
+    Projects,
+    Blog,
+    /// The slug of a tag.
+    Tag(String),
+    Post(String),
+}
+
+impl Page {
+    /// The path of the page in the capsule.
+    pub(crate) fn gemini_path(&self) -> String {
+        match self {
+            Self::Home => "/".to_owned(),
+            Self::Projects => "/projects.gmi".to_owned(),
+            Self::Blog => "/blog/".to_owned(),
+            Self::Tag(tag) => format!("/tags/{tag}.gmi"),
+            Self::Post(name) => format!("/blog/{name}.gmi"),
+        }
+    }
+
+    /// The file of the page in the capsule, relative to its root.
+    fn gemini_file(&self) -> String {
+        let path = self.gemini_path();
+        match path.strip_suffix('/') {
+            Some(dir) => format!("{dir}/index.gmi")
+                .trim_start_matches('/')
+                .to_owned(),
+            None => path.trim_start_matches('/').to_owned(),
+        }
+    }
+}
+
+/// Renders every page of the site as gemtext, along with the file (relative to the capsule's
+/// root) that it goes in.
+pub(crate) fn capsule(site: &Site) -> Vec<(String, String)> {
+    pages(site)
+        .into_iter()
+        .map(|(page, lines)| (page.gemini_file(), gemtext(&lines)))
+        .collect()
+}
+
+/// Every page of the site as lines.
+pub(crate) fn pages(site: &Site) -> Vec<(Page, Vec<Line>)> {
+    let title = &site.config.title;
+    let newest_first: Vec<_> = site.posts.iter().rev().map(|post| &post.summary).collect();
+    let mut tags: BTreeMap<String, (&str, Vec<&PostSummary>)> = BTreeMap::new();
+    for post in &newest_first {
+        for tag in &post.tags {
+            tags.entry(slugify(tag))
+                .or_insert_with(|| (tag, Vec::new()))
+                .1
+                .push(post);
+        }
+    }
+
+    let mut home = vec![Line::Heading(1, title.clone()), Line::Blank];
+    home.extend(lines(&site.home.body, site));
+    home.extend([
+        Line::Blank,
+        Line::Link(Target::Page(Page::Blog), "Blog".to_owned()),
+        Line::Link(Target::Page(Page::Projects), "Projects".to_owned()),
+    ]);
+    home.extend(
+        site.config
+            .site_info()
+            .links
+            .into_iter()
+            .filter_map(|item| Some(Line::Link(target(&item.url, site)?, item.name))),
+    );
+
+    let mut projects = vec![Line::Heading(1, "Projects".to_owned()), Line::Blank];
+    projects.extend(lines(&site.projects, site));
+    projects.extend([Line::Blank, home_link(site)]);
+
+    let mut blog = vec![Line::Heading(1, title.clone()), Line::Blank];
+    blog.extend(newest_first.iter().map(|post| index_link(post)));
+    if !tags.is_empty() {
+        blog.extend([Line::Blank, Line::Heading(2, "Tags".to_owned())]);
+        blog.extend(tags.iter().map(|(slug, (tag, posts))| {
+            Line::Link(
+                Target::Page(Page::Tag(slug.clone())),
+                format!("{tag} ({})", posts.len()),
+            )
+        }));
+    }
+    blog.extend([Line::Blank, home_link(site)]);
+
+    let mut digest = vec![
+        (Page::Home, home),
+        (Page::Projects, projects),
+        (Page::Blog, blog),
+    ];
+    for (slug, (tag, posts)) in &tags {
+        let mut page = vec![Line::Heading(1, format!("Posts tagged {tag}")), Line::Blank];
+        page.extend(posts.iter().map(|post| index_link(post)));
+        page.extend([Line::Blank, blog_link()]);
+        digest.push((Page::Tag(slug.clone()), page));
+    }
+    for post in site.posts.iter() {
+        let summary = &post.summary;
+        let mut date = format!("Published {}", summary.create_on);
+        if let Some(edit) = summary.last_edit {
+            date.push_str(&format!(", updated {}", edit.format("%Y-%m-%d")));
+        }
+        let mut page = vec![
+            Line::Heading(1, summary.title.clone()),
+            Line::Text(date),
+            Line::Blank,
+        ];
+        page.extend(lines(&post.body, site));
+        page.push(Line::Blank);
+        page.extend(summary.tags.iter().map(|tag| {
+            Line::Link(
+                Target::Page(Page::Tag(slugify(tag))),
+                format!("More posts about {tag}"),
+            )
+        }));
+        page.push(blog_link());
+        digest.push((Page::Post(summary.real_name.clone()), page));
+    }
+    digest
+}
+
+fn home_link(site: &Site) -> Line {
+    Line::Link(Target::Page(Page::Home), site.config.title.clone())
+}
+
+fn blog_link() -> Line {
+    Line::Link(Target::Page(Page::Blog), "All posts".to_owned())
+}
+
+/// A post's link in an index. Starting with the date lets Gemini clients subscribe to the index
+/// like a feed.
+fn index_link(post: &PostSummary) -> Line {
+    Line::Link(
+        Target::Page(Page::Post(post.real_name.clone())),
+        format!("{} - {}", post.create_on, post.title),
+    )
+}
+
+/// Where a link of the site leads. Links within the site have already been rewritten into the
+/// TUI's routes, which are mapped onto the pages for the small web. Anchors are dropped, since
+/// neither Gemini nor Gopher has them, as are links to somewhere on the same page.
+fn target(url: &str, site: &Site) -> Option<Target> {
+    if url.starts_with('#') {
+        return None;
+    }
+    if !url.starts_with('/') {
+        return Some(Target::Url(url.to_owned()));
+    }
+    let route = url
+        .strip_prefix(&site.config.base_path())
+        .filter(|route| route.is_empty() || route.starts_with(['/', '#']))
+        .map(|route| route.split('#').next().unwrap_or_default());
+    let page = match route.map(|route| route.trim_matches('/')) {
+        Some("") => Some(Page::Home),
+        Some("projects") => Some(Page::Projects),
+        Some("blog" | "archive") => Some(Page::Blog),
+        Some(route) => route
+            .strip_prefix("blog/")
+            .map(|name| Page::Post(name.to_owned())),
+        None => None,
+    };
+    match page {
+        Some(page) => Some(Target::Page(page)),
+        // Anything else on the site (e.g. an image) is only on the web
+        None => Some(Target::Url(format!("{}{url}", site.config.origin()))),
+    }
+}
+
+/// Turns markdown into lines. Gemtext doesn't have links within text, so the links of each
+/// paragraph (or list) are hoisted onto lines of their own right after it.
+fn lines(md: &Markdown, site: &Site) -> Vec<Line> {
+    let mut digest = Vec::new();
+    for node in &md.0 {
+        let mut links = Vec::new();
+        let block: Vec<_> = match node {
+            MdNode::List(items) => items
+                .iter()
+                .map(|item| Line::Item(inline(item, site, &mut links).replace('\n', " ")))
+                .collect(),
+            MdNode::Code(code) => {
+                let source: String = code.tokens.iter().map(|(text, _)| text.as_str()).collect();
+                vec![Line::Preformatted(
+                    code.info.lang.clone(),
+                    source.lines().map(str::to_owned).collect(),
+                )]
+            }
+            MdNode::BlockQuote(text) => text
+                .lines()
+                .map(|line| Line::Quote(line.to_owned()))
+                .collect(),
+            MdNode::Heading(text) => vec![Line::Heading(2, text.clone())],
+            MdNode::ThematicBreak => vec![Line::Text("---".to_owned())],
+            node => inline(node, site, &mut links)
+                .lines()
+                .map(|line| Line::Text(line.trim_end().to_owned()))
+                .collect(),
+        };
+        // Some nodes (like the HTML that is dropped) have nothing to show
+        if block.is_empty() && links.is_empty() {
+            continue;
+        }
+        if !digest.is_empty() {
+            digest.push(Line::Blank);
+        }
+        digest.extend(block);
+        digest.extend(links);
+    }
+    digest
+}
+
+/// The text of a node within a paragraph, with its links added to the given lines.
+fn inline(node: &MdNode, site: &Site, links: &mut Vec<Line>) -> String {
+    match node {
+        MdNode::Paragraph(nodes) | MdNode::List(nodes) => {
+            nodes.iter().map(|node| inline(node, site, links)).collect()
+        }
+        MdNode::Text(text) | MdNode::Emphasis(text) | MdNode::Strong(text) => text.clone(),
+        MdNode::InlineCode(text) => format!("`{text}`"),
+        MdNode::Link(text, url) => {
+            links.extend(target(url, site).map(|target| Line::Link(target, text.clone())));
+            text.clone()
+        }
+        MdNode::Break => "\n".to_owned(),
+        MdNode::Code(_) | MdNode::BlockQuote(_) | MdNode::Heading(_) | MdNode::ThematicBreak => {
+            String::new()
+        }
+    }
+}
+
+/// Writes lines as gemtext.
+pub(crate) fn gemtext(lines: &[Line]) -> String {
+    let mut digest = String::new();
+    for line in lines {
+        match line {
+            Line::Text(text) => {
+                // Text that looks like another kind of line is kept from being read as one
+                let markers = ["=>", "#", "* ", ">", "```"];
+                if markers.iter().any(|marker| text.starts_with(marker)) {
+                    digest.push(' ');
+                }
+                digest.push_str(text);
+            }
+            Line::Heading(level, text) => {
+                digest.push_str(&format!("{} {text}", "#".repeat(*level as usize)))
+            }
+            Line::Item(text) => digest.push_str(&format!("* {text}")),
+            Line::Quote(text) => digest.push_str(&format!("> {text}")),
+            Line::Link(target, text) => {
+                let url = match target {
+                    Target::Page(page) => page.gemini_path(),
+                    Target::Url(url) => url.clone(),
+                };
+                digest.push_str(&format!("=> {url} {text}"));
+            }
+            Line::Preformatted(lang, code) => {
+                digest.push_str(&format!("```{}\n", lang.as_deref().unwrap_or_default()));
+                for line in code {
+                    if line.starts_with("```") {
+                        digest.push(' ');
+                    }
+                    digest.push_str(line);
+                    digest.push('\n');
+                }
+                digest.push_str("```");
+            }
+            Line::Blank => {}
+        }
+        digest.push('\n');
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use avid_rustacean_model::{Markdown, MdNode};
+
+    use super::capsule;
+    use crate::{site::Site, testing};
+
+    fn site() -> Site {
+        let mut body: Markdown = "## Intro\n\nSee [the old post](/tui/blog/old#end), \
+            [the docs](https://docs.rs), and [an image](/images/crab.png).\n\n\
+            ```rust\nfn main() {}\n```\n\n=> Not a link"
+            .parse()
+            .unwrap();
+        body.0.insert(
+            2,
+            MdNode::List(vec![
+                MdNode::Paragraph(vec![MdNode::Text("one".to_owned())]),
+                MdNode::Paragraph(vec![MdNode::Link(
+                    "two".to_owned(),
+                    "/tui/archive".to_owned(),
+                )]),
+            ]),
+        );
+        let mut site = testing::site();
+        site.home.body = "Hello, and *welcome*!".parse().unwrap();
+        site.posts[0].body = "Old news.".parse().unwrap();
+        site.posts[1].body = body;
+        site
+    }
+
+    #[test]
+    fn pages_become_gemtext() {
+        let capsule: HashMap<_, _> = capsule(&site()).into_iter().collect();
+        let mut names: Vec<_> = capsule.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "blog/index.gmi",
+                "blog/new.gmi",
+                "blog/old.gmi",
+                "index.gmi",
+                "projects.gmi",
+                "tags/rust.gmi",
+                "tags/web-dev.gmi"
+            ]
+        );
+        assert_eq!(
+            capsule["blog/new.gmi"],
+            "# The new post\n\
+             Published 2024-02-28\n\
+             \n\
+             ## Intro\n\
+             \n\
+             See the old post, the docs, and an image.\n\
+             => /blog/old.gmi the old post\n\
+             => https://docs.rs the docs\n\
+             => https://example.com/images/crab.png an image\n\
+             \n\
+             * one\n\
+             * two\n\
+             => /blog/ two\n\
+             \n\
+             ```rust\n\
+             fn main() {}\n\
+             ```\n\
+             \n\
+             \x20=> Not a link\n\
+             \n\
+             => /tags/rust.gmi More posts about Rust\n\
+             => /tags/web-dev.gmi More posts about Web Dev\n\
+             => /blog/ All posts\n"
+        );
+        // The index can be subscribed to, since its links start with dates
+        assert_eq!(
+            capsule["blog/index.gmi"],
+            "# Example\n\
+             \n\
+             => /blog/new.gmi 2024-02-28 - The new post\n\
+             => /blog/old.gmi 2023-12-17 - The old post\n\
+             \n\
+             ## Tags\n\
+             => /tags/rust.gmi Rust (2)\n\
+             => /tags/web-dev.gmi Web Dev (1)\n\
+             \n\
+             => / Example\n"
+        );
+        assert!(
+            capsule["tags/web-dev.gmi"].contains("=> /blog/new.gmi 2024-02-28 - The new post\n")
+        );
+        assert!(!capsule["tags/web-dev.gmi"].contains("old"));
+        assert!(capsule["index.gmi"]
+            .starts_with("# Example\n\nHello, and welcome!\n\n=> /blog/ Blog\n"));
+    }
+}
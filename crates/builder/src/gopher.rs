@@ -0,0 +1,147 @@
+use crate::{
+    gemtext::{pages, Line, Page, Target},
+    site::Site,
+};
+
+/// The number of columns that text is wrapped to, since Gopher clients show menus as they are.
+const WIDTH: usize = 70;
+
+impl Page {
+    /// The selector of the page in the gopher hole. Every page is a directory with a gophermap.
+    pub(crate) fn selector(&self) -> String {
+        match self {
+            Self::Home => "/".to_owned(),
+            Self::Projects => "/projects/".to_owned(),
+            Self::Blog => "/blog/".to_owned(),
+            Self::Tag(tag) => format!("/tags/{tag}/"),
+            Self::Post(name) => format!("/blog/{name}/"),
+        }
+    }
+}
+
+/// Renders every page of the site as a gophermap, along with the file (relative to the hole's
+/// root) that it goes in.
+pub(crate) fn hole(site: &Site) -> Vec<(String, String)> {
+    pages(site)
+        .into_iter()
+        .map(|(page, lines)| {
+            let file = format!("{}gophermap", page.selector().trim_start_matches('/'));
+            (file, gophermap(&lines))
+        })
+        .collect()
+}
+
+/// Writes lines as a gophermap. Text becomes info lines and links become menu items, with the
+/// links that leave the hole using the `URL:` convention. The host and port of each line are left
+/// out, which servers like Gophernicus fill in with their own.
+pub(crate) fn gophermap(lines: &[Line]) -> String {
+    let mut digest = String::new();
+    for line in lines {
+        let text = match line {
+            Line::Text(text) => wrap(text, "", ""),
+            Line::Heading(level, text) => {
+                let rule = if *level == 1 { "=" } else { "-" };
+                vec![text.clone(), rule.repeat(text.chars().count())]
+            }
+            Line::Item(text) => wrap(text, "* ", "  "),
+            Line::Quote(text) => wrap(text, "> ", "> "),
+            Line::Preformatted(_, code) => code.clone(),
+            Line::Link(target, text) => {
+                let text = text.replace('\t', " ");
+                match target {
+                    Target::Page(page) => {
+                        digest.push_str(&format!("1{text}\t{}\n", page.selector()))
+                    }
+                    Target::Url(url) => digest.push_str(&format!("h{text}\tURL:{url}\n")),
+                }
+                continue;
+            }
+            Line::Blank => vec![String::new()],
+        };
+        for line in text {
+            digest.push_str(&format!("i{}\t\n", line.replace('\t', "    ")));
+        }
+    }
+    digest
+}
+
+/// Wraps text at the spaces between its words, starting the first line and every other line with
+/// the given prefixes. Words that are longer than a line are left whole.
+fn wrap(text: &str, first: &str, rest: &str) -> Vec<String> {
+    let mut digest = Vec::new();
+    let mut line = first.to_owned();
+    let mut empty = true;
+    for word in text.split_whitespace() {
+        if !empty && line.chars().count() + 1 + word.chars().count() > WIDTH {
+            digest.push(std::mem::replace(&mut line, rest.to_owned()));
+            empty = true;
+        }
+        if !empty {
+            line.push(' ');
+        }
+        line.push_str(word);
+        empty = false;
+    }
+    digest.push(line);
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gophermap, wrap};
+    use crate::gemtext::{Line, Page, Target};
+
+    #[test]
+    fn lines_become_a_gophermap() {
+        let lines = [
+            Line::Heading(1, "A Post".to_owned()),
+            Line::Text("Some text.".to_owned()),
+            Line::Link(
+                Target::Page(Page::Post("other".to_owned())),
+                "Another".to_owned(),
+            ),
+            Line::Link(
+                Target::Url("https://docs.rs".to_owned()),
+                "The docs".to_owned(),
+            ),
+            Line::Blank,
+            Line::Item("one".to_owned()),
+            Line::Preformatted(
+                Some("rust".to_owned()),
+                vec![
+                    "fn main() {".to_owned(),
+                    "\tdbg!();".to_owned(),
+                    "}".to_owned(),
+                ],
+            ),
+        ];
+        assert_eq!(
+            gophermap(&lines),
+            "iA Post\t\n\
+             i======\t\n\
+             iSome text.\t\n\
+             1Another\t/blog/other/\n\
+             hThe docs\tURL:https://docs.rs\n\
+             i\t\n\
+             i* one\t\n\
+             ifn main() {\t\n\
+             i    dbg!();\t\n\
+             i}\t\n"
+        );
+    }
+
+    #[test]
+    fn text_is_wrapped() {
+        let text = "word ".repeat(20);
+        let lines = wrap(&text, "* ", "  ");
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 66);
+        assert!(lines[0].starts_with("* word"));
+        assert!(lines[1].starts_with("  word"));
+        let long = "x".repeat(100);
+        assert_eq!(
+            wrap(&format!("a {long} b"), "", ""),
+            ["a", long.as_str(), "b"]
+        );
+    }
+}
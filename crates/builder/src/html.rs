@@ -0,0 +1,166 @@
+use avid_rustacean_model::{origin, slugify, Markdown, MdNode};
+
+/// Renders markdown as HTML for places outside of the TUI, like feed readers. Links to the TUI's
+/// routes (and any other root-relative links) are made absolute using the origin of the base URL
+/// since the HTML is read away from the site.
+pub(crate) fn render(md: &Markdown, base_url: &str) -> String {
+    let mut html = String::new();
+    for node in &md.0 {
+        render_node(&mut html, node, base_url);
+    }
+    html
+}
+
+fn render_node(html: &mut String, node: &MdNode, base_url: &str) {
+    match node {
+        MdNode::Paragraph(nodes) => {
+            html.push_str("<p>");
+            for node in nodes {
+                render_node(html, node, base_url);
+            }
+            html.push_str("</p>\n");
+        }
+        MdNode::List(items) => {
+            html.push_str("<ul>\n");
+            for item in items {
+                html.push_str("<li>");
+                render_node(html, item, base_url);
+                html.push_str("</li>\n");
+            }
+            html.push_str("</ul>\n");
+        }
+        MdNode::Code(code) => {
+            match &code.info.lang {
+                Some(lang) => {
+                    html.push_str(&format!(r#"<pre><code class="language-{}">"#, escape(lang)))
+                }
+                None => html.push_str("<pre><code>"),
+            }
+            for (text, _) in &code.tokens {
+                html.push_str(&escape(text));
+            }
+            html.push_str("</code></pre>\n");
+        }
+        MdNode::BlockQuote(text) => html.push_str(&format!(
+            "<blockquote><p>{}</p></blockquote>\n",
+            escape(text)
+        )),
+        MdNode::InlineCode(text) => html.push_str(&format!("<code>{}</code>", escape(text))),
+        MdNode::Emphasis(text) => html.push_str(&format!("<em>{}</em>", escape(text))),
+        MdNode::Strong(text) => html.push_str(&format!("<strong>{}</strong>", escape(text))),
+        MdNode::Link(text, url) => {
+            let url = match url.strip_prefix('/') {
+                Some(path) => format!("{}/{path}", origin(base_url)),
+                None => url.clone(),
+            };
+            html.push_str(&format!(
+                r#"<a href="{}">{}</a>"#,
+                escape(&url),
+                escape(text)
+            ));
+        }
+        MdNode::Heading(text) => html.push_str(&format!(
+            r#"<h2 id="{}">{}</h2>
+"#,
+            slugify(text),
+            escape(text)
+        )),
+        MdNode::Text(text) => html.push_str(&escape(text)),
+        MdNode::ThematicBreak => html.push_str("<hr>\n"),
+        MdNode::Break => html.push_str("<br>\n"),
+    }
+}
+
+/// The name of the `<meta>` tag that tells the frontend the path that it is served under.
+pub(crate) const BASE_PATH_META: &str = "tui-base";
+
+/// Adds a `<meta>` tag to the start of an HTML page's `<head>`.
+pub(crate) fn inject_meta(html: &str, name: &str, content: &str) -> String {
+    let meta = format!(
+        r#"<meta name="{}" content="{}">"#,
+        escape(name),
+        escape(content)
+    );
+    match html.find("<head>") {
+        Some(i) => {
+            let i = i + "<head>".len();
+            format!("{}{meta}{}", &html[..i], &html[i..])
+        }
+        None => format!("{meta}{html}"),
+    }
+}
+
+/// Adds HTML to the start of an HTML page's `<body>`.
+pub(crate) fn inject_body(html: &str, content: &str) -> String {
+    let start = html
+        .find("<body")
+        .and_then(|i| html[i..].find('>').map(|end| i + end + 1));
+    match start {
+        Some(i) => format!("{}{content}{}", &html[..i], &html[i..]),
+        None => format!("{html}{content}"),
+    }
+}
+
+/// A page that sends the browser to another URL, for the old routes of posts (see the `aliases`
+/// in their front matter). This works without JavaScript and tells crawlers where the post is now.
+pub(crate) fn redirect_page(url: &str) -> String {
+    let url = escape(url);
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Redirecting…</title>
+<link rel="canonical" href="{url}">
+<meta http-equiv="refresh" content="0; url={url}">
+</head>
+<body><a href="{url}">This post has moved.</a></body>
+</html>
+"#
+    )
+}
+
+/// Escapes text so that it can be used in HTML or XML, including in attributes.
+pub(crate) fn escape(text: &str) -> String {
+    let mut digest = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => digest.push_str("&amp;"),
+            '<' => digest.push_str("&lt;"),
+            '>' => digest.push_str("&gt;"),
+            '"' => digest.push_str("&quot;"),
+            '\'' => digest.push_str("&#39;"),
+            c => digest.push(c),
+        }
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use avid_rustacean_model::Markdown;
+
+    use super::render;
+
+    #[test]
+    fn render_markdown() {
+        let md: Markdown = "## Fish & Chips\n\nSome *text* with [a link](/tui/blog/post#a) and \
+            [another](https://example.com?a=1&b=2).\n\n```rust\nlet x = 1 < 2;\n```"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            render(&md, "https://avid-rustacean.dev"),
+            "<h2 id=\"fish-chips\">Fish &amp; Chips</h2>\n\
+             <p>Some <em>text</em> with \
+             <a href=\"https://avid-rustacean.dev/tui/blog/post#a\">a link</a> and \
+             <a href=\"https://example.com?a=1&amp;b=2\">another</a>.</p>\n\
+             <pre><code class=\"language-rust\">let x = 1 &lt; 2;</code></pre>\n"
+        );
+        // The links already include the path of the base URL
+        let md: Markdown = "[a link](/blog/tui/blog/post)".parse().unwrap();
+        assert_eq!(
+            render(&md, "https://example.com/blog/"),
+            "<p><a href=\"https://example.com/blog/tui/blog/post\">a link</a></p>\n"
+        );
+    }
+}
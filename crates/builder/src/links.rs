@@ -0,0 +1,352 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use avid_rustacean_model::{post_slug, slugify, Markdown};
+use ureq::Agent;
+
+/// How long an external link has to respond before it is considered broken.
+pub(crate) const LINK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where a link in a page points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LinkTarget<'a> {
+    /// A link that leaves the site, e.g. `https://...` or `mailto:...`.
+    External(&'a str),
+    /// A link to a post (by its name) with an optional heading anchor. These are written as either
+    /// Zola paths (`@/some-post.md`) or as URLs (`/blog/some-post`).
+    Post(&'a str, Option<&'a str>),
+    /// A link to a heading in the same page, e.g. `#intro`.
+    Anchor(&'a str),
+    /// A link to some other file, e.g. an image or a non-post page.
+    Asset(&'a str),
+}
+
+impl<'a> LinkTarget<'a> {
+    pub(crate) fn parse(url: &'a str) -> Self {
+        if url.contains("://") || url.starts_with("mailto:") {
+            return Self::External(url);
+        }
+        if let Some(anchor) = url.strip_prefix('#') {
+            return Self::Anchor(anchor);
+        }
+        let (path, anchor) = match url.split_once('#') {
+            Some((path, anchor)) => (path, Some(anchor)),
+            None => (url, None),
+        };
+        let post = path
+            .strip_prefix("@/")
+            .and_then(|p| p.strip_suffix(".md"))
+            .or_else(|| path.strip_prefix("/tui/blog/"))
+            .or_else(|| path.strip_prefix("/blog/"))
+            .map(|p| p.trim_end_matches('/'))
+            .filter(|p| !p.is_empty() && !p.contains('/'));
+        match post {
+            Some(name) => Self::Post(name, anchor),
+            None => Self::Asset(path.strip_prefix("@/").unwrap_or(path)),
+        }
+    }
+}
+
+/// A link that does not point anywhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LinkError {
+    /// The page that contains the link.
+    pub(crate) page: String,
+    pub(crate) url: String,
+    pub(crate) reason: String,
+}
+
+/// Checks the links in pages against the posts that exist and the files in the content directory.
+#[derive(Debug)]
+pub(crate) struct LinkChecker {
+    /// The anchors of the headings in each post, keyed by the post's slug.
+    posts: HashMap<String, HashSet<String>>,
+    content: PathBuf,
+    /// The path that the TUI is served under, e.g. `/tui`.
+    base_path: String,
+}
+
+impl LinkChecker {
+    pub(crate) fn new<'b, I>(posts: I, content: &Path, base_path: &str) -> Self
+    where
+        I: IntoIterator<Item = (&'b str, &'b Markdown)>,
+    {
+        let posts = posts
+            .into_iter()
+            .map(|(name, md)| (name.to_owned(), anchors(md)))
+            .collect();
+        Self {
+            posts,
+            content: content.to_owned(),
+            base_path: base_path.to_owned(),
+        }
+    }
+
+    /// Checks all of the internal links in a page. The page's name is used for error messages.
+    pub(crate) fn check(&self, page: &str, md: &Markdown) -> Vec<LinkError> {
+        let own_anchors = anchors(md);
+        md.links()
+            .into_iter()
+            .filter_map(|(_, url)| {
+                let reason = match LinkTarget::parse(url) {
+                    LinkTarget::External(_) => None,
+                    LinkTarget::Anchor(anchor) => (!own_anchors.contains(anchor))
+                        .then(|| format!("no heading with the anchor `{anchor}`")),
+                    LinkTarget::Post(name, anchor) => match self.find_post(name) {
+                        None => Some(format!("no post named `{name}`")),
+                        Some((_, anchors)) => anchor
+                            .filter(|anchor| !anchors.contains(*anchor))
+                            .map(|anchor| format!("`{name}` has no heading `{anchor}`")),
+                    },
+                    LinkTarget::Asset(path) => {
+                        let path = path.trim_start_matches('/');
+                        (!self.content.join(path).exists())
+                            .then(|| format!("no file at `{}`", self.content.join(path).display()))
+                    }
+                };
+                reason.map(|reason| LinkError {
+                    page: page.to_owned(),
+                    url: url.to_owned(),
+                    reason,
+                })
+            })
+            .collect()
+    }
+
+    /// Rewrites the internal links in a page into the TUI's routes. Links to other posts (via Zola
+    /// paths or blog URLs) become `/tui/blog/<name>` (under the base path), keeping any anchor. If
+    /// the page is a post, links to its own headings become `/tui/blog/<page>#<anchor>` so that the
+    /// frontend can route them. Links that can not be resolved are left alone; those are reported by
+    /// [`LinkChecker::check`].
+    pub(crate) fn rewrite(&self, page: &str, md: &mut Markdown) {
+        md.visit_links_mut(|url| {
+            let route = match LinkTarget::parse(url) {
+                LinkTarget::Post(name, anchor) => self
+                    .find_post(name)
+                    .map(|(name, _)| self.post_route(name, anchor)),
+                LinkTarget::Anchor(anchor) => self
+                    .posts
+                    .contains_key(page)
+                    .then(|| self.post_route(page, Some(anchor))),
+                LinkTarget::External(_) | LinkTarget::Asset(_) => None,
+            };
+            if let Some(route) = route {
+                *url = route;
+            }
+        });
+    }
+
+    /// The frontend's route for a post.
+    fn post_route(&self, name: &str, anchor: Option<&str>) -> String {
+        match anchor {
+            Some(anchor) => format!("{}/blog/{name}#{anchor}", self.base_path),
+            None => format!("{}/blog/{name}", self.base_path),
+        }
+    }
+
+    /// Finds a post by any spelling of its name (e.g. the name of its file in a Zola path), which
+    /// is turned into its slug. Returns the post's slug and the anchors of its headings.
+    fn find_post(&self, name: &str) -> Option<(&str, &HashSet<String>)> {
+        self.posts
+            .get_key_value(&post_slug(name))
+            .map(|(name, anchors)| (name.as_str(), anchors))
+    }
+}
+
+/// Collects the anchors of every heading in a document.
+fn anchors(md: &Markdown) -> HashSet<String> {
+    md.headings().map(slugify).collect()
+}
+
+/// Checks that each external link can be reached. By default, each URL is requested directly.
+/// If an endpoint is given, it is asked about each URL instead (`<endpoint>?url=<url>`), which
+/// allows a link checking service or a local stub to stand in. Any response other than a 2xx or
+/// 3xx, or no response within the timeout, is considered broken.
+pub(crate) fn check_external<'b, I>(
+    links: I,
+    endpoint: Option<&str>,
+    timeout: Duration,
+) -> Vec<LinkError>
+where
+    I: IntoIterator<Item = (&'b str, &'b str)>,
+{
+    let agent: Agent = Agent::config_builder()
+        .timeout_global(Some(timeout))
+        .build()
+        .into();
+    let mut checked: HashMap<&str, Option<String>> = HashMap::new();
+    let mut digest = Vec::new();
+    for (page, url) in links {
+        let LinkTarget::External(url) = LinkTarget::parse(url) else {
+            continue;
+        };
+        if url.starts_with("mailto:") {
+            continue;
+        }
+        let reason = checked.entry(url).or_insert_with(|| {
+            let request = match endpoint {
+                Some(endpoint) => format!("{endpoint}?url={}", url_encode(url)),
+                None => url.to_owned(),
+            };
+            agent.get(&request).call().err().map(|err| match err {
+                ureq::Error::Timeout(_) => {
+                    format!("no response within {} seconds", timeout.as_secs_f32())
+                }
+                err => err.to_string(),
+            })
+        });
+        if let Some(reason) = reason {
+            digest.push(LinkError {
+                page: page.to_owned(),
+                url: url.to_owned(),
+                reason: reason.clone(),
+            });
+        }
+    }
+    digest
+}
+
+fn url_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        path::Path,
+        time::Duration,
+    };
+
+    use avid_rustacean_model::Markdown;
+
+    use super::{check_external, LinkChecker, LinkTarget, LINK_TIMEOUT};
+
+    #[test]
+    fn parse_targets() {
+        assert_eq!(
+            LinkTarget::parse("https://github.com"),
+            LinkTarget::External("https://github.com")
+        );
+        assert_eq!(LinkTarget::parse("#intro"), LinkTarget::Anchor("intro"));
+        assert_eq!(
+            LinkTarget::parse("@/about-this-blog.md#intro"),
+            LinkTarget::Post("about-this-blog", Some("intro"))
+        );
+        assert_eq!(
+            LinkTarget::parse("/blog/about-this-blog/"),
+            LinkTarget::Post("about-this-blog", None)
+        );
+        assert_eq!(
+            LinkTarget::parse("@/pages/projects.md"),
+            LinkTarget::Asset("pages/projects.md")
+        );
+        assert_eq!(LinkTarget::parse("img.png"), LinkTarget::Asset("img.png"));
+    }
+
+    #[test]
+    fn internal_links() {
+        let other: Markdown = "## The Intro\n\nHello".parse().unwrap();
+        let content = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let checker = LinkChecker::new([("other-post", &other)], content, "/tui");
+
+        let page: Markdown = "# Top\n\n[a](#top) [b](@/other-post.md#the-intro) [c](/blog/other-post) [d](Cargo.toml) [e](https://example.com)"
+            .parse()
+            .unwrap();
+        assert_eq!(checker.check("page", &page), Vec::new());
+
+        let page: Markdown =
+            "[a](#nope) [b](@/missing.md) [c](@/other-post.md#nope) [d](missing.png)"
+                .parse()
+                .unwrap();
+        let errors = checker.check("page", &page);
+        assert_eq!(errors.len(), 4);
+        assert!(errors.iter().all(|e| e.page == "page"));
+    }
+
+    #[test]
+    fn rewrite_internal_links() {
+        let other: Markdown = "## The Intro\n\nHello".parse().unwrap();
+        let content = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let checker = LinkChecker::new([("other-post", &other)], content, "/tui");
+
+        // Links can use any spelling of the post's name, like the name of its file
+        let mut page: Markdown = "[a](#top) [b](@/Other_Post.md#the-intro) [c](/blog/Other%20Post/) [d](https://example.com) [e](@/missing.md)"
+            .parse()
+            .unwrap();
+        checker.rewrite("other-post", &mut page);
+        let urls: Vec<_> = page.links().into_iter().map(|(_, url)| url).collect();
+        assert_eq!(
+            urls,
+            [
+                "/tui/blog/other-post#top",
+                "/tui/blog/other-post#the-intro",
+                "/tui/blog/other-post",
+                "https://example.com",
+                "@/missing.md",
+            ]
+        );
+
+        // Anchors in pages that are not posts stay as they are
+        let mut page: Markdown = "[a](#top)".parse().unwrap();
+        checker.rewrite("home.md", &mut page);
+        assert_eq!(page.links()[0].1, "#top");
+    }
+
+    #[test]
+    fn external_links_via_endpoint() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/check", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            for stream in listener.incoming().take(2) {
+                let mut stream = stream.unwrap();
+                let mut request = String::new();
+                BufReader::new(&stream).read_line(&mut request).unwrap();
+                let status = if request.contains("good") {
+                    "200 OK"
+                } else {
+                    "404 Not Found"
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                )
+                .unwrap();
+            }
+        });
+
+        let links = [
+            ("page", "https://good.example.com"),
+            ("page", "https://bad.example.com"),
+            // Duplicates are only checked once, and non-external links are ignored
+            ("other", "https://good.example.com"),
+            ("other", "#anchor"),
+        ];
+        let errors = check_external(links, Some(&endpoint), LINK_TIMEOUT);
+        server.join().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].url, "https://bad.example.com");
+    }
+
+    #[test]
+    fn silent_links_are_broken() {
+        // The connection is accepted, but nothing is ever sent back
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let errors = check_external([("page", url.as_str())], None, Duration::from_millis(200));
+        drop(listener);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].reason, "no response within 0.2 seconds");
+    }
+}
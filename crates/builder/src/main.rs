@@ -13,20 +13,232 @@
     clippy::all
 )]
 
-use std::{collections::HashMap, path::PathBuf};
+use std::path::PathBuf;
 
-use avid_rustacean_model::{split_markdown, HomePage, Markdown, Post, PostSummary};
-use toml::Value;
+use avid_rustacean_model::CodeTheme;
+use cache::BuildCache;
+use clap::{Args, Parser, Subcommand};
+use diagnostics::{exit_with, Diagnostic};
+use site::Site;
+
+mod assemble;
+mod cache;
+mod config;
+mod diagnostics;
+mod feeds;
+mod gemini;
+mod gemtext;
+mod gopher;
+mod html;
+mod links;
+mod serve;
+mod shortcodes;
+mod site;
+mod sitemap;
+mod snapshots;
+mod snippets;
+#[cfg(test)]
+mod testing;
+mod text;
+
+/// Builds the JSON that the web TUI is made from, using the same content and config as Zola.
+#[derive(Debug, Parser)]
+struct Cli {
+    /// The Zola content directory.
+    #[arg(long, global = true, default_value = "content")]
+    content: PathBuf,
+    /// The directory that the generated files are written to.
+    #[arg(long, global = true, default_value = "crates/assets")]
+    out: PathBuf,
+    /// The Zola config file.
+    #[arg(long, global = true, default_value = "config.toml")]
+    config: PathBuf,
+    /// The file that parsed pages are cached in between builds.
+    #[arg(long, global = true, default_value = "target/builder-cache.json")]
+    cache: PathBuf,
+    /// What to do. Defaults to `build`.
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Generates all of the JSON for the site.
+    Build(BuildOptions),
+    /// Parses the content and checks its links without writing anything.
+    Check(BuildOptions),
+    /// Builds the frontend and the JSON and lays out the whole site the way it is deployed.
+    Assemble(AssembleOptions),
+    /// Builds the site, serves it locally, and rebuilds it whenever the content changes.
+    Serve(ServeOptions),
+    /// Builds the site and serves its Gemini capsule locally.
+    Gemini(GeminiOptions),
+    /// Removes the output directory and the build cache.
+    Clean,
+    /// Compile-checks the Rust code blocks in every post.
+    TestSnippets,
+}
+
+/// The options for building the site.
+#[derive(Debug, Default, Args)]
+struct BuildOptions {
+    /// A `.tmTheme` file used to color highlighted code.
+    #[arg(long)]
+    theme: Option<PathBuf>,
+    /// Whether or not external links are checked.
+    #[arg(long)]
+    check_external: bool,
+    /// An endpoint that is asked about each external link rather than requesting the link itself.
+    #[arg(long)]
+    link_endpoint: Option<String>,
+}
+
+/// The options for assembling the site.
+#[derive(Debug, Args)]
+struct AssembleOptions {
+    /// The directory that the site is laid out in. Zola copies it into the rest of the site.
+    #[arg(long, default_value = "static")]
+    dist: PathBuf,
+    /// The frontend's crate, which is built with Trunk.
+    #[arg(long, default_value = "crates/frontend")]
+    frontend: PathBuf,
+    /// Use the frontend that is already in the output directory rather than building it with Trunk.
+    #[arg(long)]
+    skip_frontend: bool,
+    #[command(flatten)]
+    build: BuildOptions,
+}
+
+/// The options for serving the site locally.
+#[derive(Debug, Args)]
+struct ServeOptions {
+    /// The port to serve the site on.
+    #[arg(long, default_value_t = 8080)]
+    port: u16,
+    #[command(flatten)]
+    build: BuildOptions,
+}
+
+/// The options for previewing the Gemini capsule.
+#[derive(Debug, Args)]
+struct GeminiOptions {
+    /// The port to serve the capsule on.
+    #[arg(long, default_value_t = 1965)]
+    port: u16,
+    #[command(flatten)]
+    build: BuildOptions,
+}
 
 fn main() {
-    // Path to assets directory
-    let mut assets_path: PathBuf = env!("CARGO_MANIFEST_DIR").parse().unwrap();
-    assets_path.pop();
-    assets_path.push("assets");
+    let cli = Cli::parse();
+    if let Err(errors) = run(&cli) {
+        exit_with(errors);
+    }
+}
+
+fn run(cli: &Cli) -> Result<(), Vec<Diagnostic>> {
+    match &cli.command {
+        None => build(cli, &BuildOptions::default()).map(drop),
+        Some(Command::Build(options)) => build(cli, options).map(drop),
+        Some(Command::Check(options)) => {
+            let site = load(cli)?;
+            check(&site, options)?;
+            println!("{} posts are good to go", site.posts.len());
+            Ok(())
+        }
+        Some(Command::Assemble(options)) => {
+            let site = build(cli, &options.build)?;
+            if !options.skip_frontend {
+                assemble::build_frontend(&options.frontend, &cli.out, site.config.site_path())
+                    .map_err(|err| vec![err])?;
+            }
+            assemble::assemble(&site, &cli.out, &options.dist).map_err(|err| vec![err])?;
+            println!("Assembled the site in {}", options.dist.display());
+            Ok(())
+        }
+        Some(Command::Serve(options)) => serve::serve(cli, options),
+        Some(Command::Gemini(options)) => gemini::serve(cli, options),
+        Some(Command::Clean) => {
+            if cli.out.exists() {
+                std::fs::remove_dir_all(&cli.out).map_err(|err| {
+                    vec![Diagnostic::new(
+                        &cli.out,
+                        format!("could not remove the output directory: {err}"),
+                    )]
+                })?;
+            }
+            if cli.cache.exists() {
+                std::fs::remove_file(&cli.cache).map_err(|err| {
+                    vec![Diagnostic::new(
+                        &cli.cache,
+                        format!("could not remove the cache: {err}"),
+                    )]
+                })?;
+            }
+            Ok(())
+        }
+        Some(Command::TestSnippets) => {
+            let site = load(cli)?;
+            // The scratch crate goes next to the cache, which is in `target/` by default
+            let scratch = cli.cache.with_file_name("snippets");
+            snippets::test_snippets(&site, &scratch)
+        }
+    }
+}
+
+/// Loads the site, using and then updating the build cache.
+fn load(cli: &Cli) -> Result<Site, Vec<Diagnostic>> {
+    let mut cache = BuildCache::load(&cli.cache);
+    let site = Site::load(&cli.content, &cli.config, &mut cache);
+    cache.save(&cli.cache);
+    println!("{} files were unchanged since the last build", cache.hits());
+    site
+}
+
+/// Loads the code theme, if one was given.
+fn load_theme(options: &BuildOptions) -> Result<Option<CodeTheme>, Diagnostic> {
+    options
+        .theme
+        .as_deref()
+        .map(|path| {
+            CodeTheme::load(path).map_err(|err| {
+                Diagnostic::new(path, format!("could not load the code theme: {err}"))
+            })
+        })
+        .transpose()
+}
 
-    if !assets_path.exists() {
-        std::fs::create_dir(&assets_path).unwrap();
+/// Checks the links of the site and loads the code theme.
+fn check(site: &Site, options: &BuildOptions) -> Result<Option<CodeTheme>, Vec<Diagnostic>> {
+    let mut errors = site.check_links(options.check_external, options.link_endpoint.as_deref());
+    let theme = load_theme(options).map_err(|err| errors.push(err)).ok();
+    match theme {
+        Some(theme) if errors.is_empty() => Ok(theme),
+        _ => Err(errors),
     }
+}
+
+/// Generates all of the JSON for the site.
+fn build(cli: &Cli, options: &BuildOptions) -> Result<Site, Vec<Diagnostic>> {
+    let mut site = load(cli)?;
+    let theme = check(&site, options)?;
+    let out = cli.out.as_path();
+    // Files that haven't changed are left alone so that their timestamps only change when their
+    // contents do
+    let write = |name: &str, data: &str| {
+        let path = out.join(name);
+        if std::fs::read(&path).is_ok_and(|old| old == data.as_bytes()) {
+            return Ok(());
+        }
+        std::fs::create_dir_all(path.parent().unwrap())
+            .and_then(|_| std::fs::write(&path, data))
+            .map_err(|err| {
+                vec![Diagnostic::new(
+                    &path,
+                    format!("could not write the file: {err}"),
+                )]
+            })
+    };
 
     // Generate badge json
     let json = r#"
@@ -36,92 +248,40 @@ fn main() {
         "message": "Active",
         "color": "8ec07c"
     }"#;
-    assets_path.push("badge.json");
-    std::fs::write(&assets_path, json).unwrap();
-    assets_path.pop();
-
-    // Collect markdown docs
-    let mut md_assets: HashMap<String, String> = std::fs::read_dir(&assets_path)
-        .unwrap()
-        .map(Result::unwrap)
-        .filter_map(|file| {
-            let name = file
-                .path()
-                .file_name()
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .to_string();
-            name.ends_with(".md").then(|| {
-                let data = std::fs::read_to_string(file.path()).unwrap();
-                (name, data)
-            })
-        })
-        .collect();
-    md_assets.remove("_index.md");
-
-    // Home page
-    let data = md_assets.remove("home.md").unwrap();
-    let (_, md) = split_markdown(&data);
-    let home = HomePage {
-        body: md.parse().unwrap(),
-    };
-    let json = serde_json::to_string(&home).unwrap();
-    assets_path.push("home.json");
-    std::fs::write(&assets_path, &json).unwrap();
-    assets_path.pop();
-
-    // Projects page
-    let data = md_assets.remove("projects.md").unwrap();
-    let (_, md) = split_markdown(&data);
-    let projects: Markdown = md.parse().unwrap();
-    let json = serde_json::to_string(&projects).unwrap();
-    assets_path.push("projects.json");
-    std::fs::write(&assets_path, &json).unwrap();
-    assets_path.pop();
-
-    // Blog
-    let mut posts = Vec::new();
-    for (path, data) in md_assets {
-        let path = path.replace(".md", ".json");
-        let (metadata, md) = split_markdown(&data);
-        let value: Value = toml::from_str(&metadata).unwrap();
-        let Value::Table(table) = value else { panic!() };
-        let Value::Datetime(created_on) = table.get("date").unwrap() else {
-            panic!()
-        };
-        let create_on = created_on.to_string();
-        let summary = PostSummary {
-            title: table.get("title").unwrap().to_string().clone(),
-            real_name: path.split_once(".json").unwrap().0.to_string(),
-            summary: table
-                .get("description")
-                .unwrap()
-                .to_string()
-                .replace(r#"""""#, "")
-                .replace(r#"'''"#, "")
-                .parse()
-                .unwrap(),
-            create_on,
-            last_edit: None,
-        };
-        posts.push((*created_on, summary.clone()));
-        let post = Post {
-            summary,
-            body: md.parse().unwrap(),
-        };
-        let json = serde_json::to_string(&post).unwrap();
-        assets_path.push(path);
-        std::fs::write(&assets_path, &json).unwrap();
-        assets_path.pop();
+    write("badge.json", json)?;
+
+    // Code theme. By default, the frontend picks the colors for highlighted code. Optionally, any
+    // `.tmTheme` file can be passed in, and its colors are quantized onto the palette.
+    if let Some(theme) = &theme {
+        write("code-theme.json", &serde_json::to_string(theme).unwrap())?;
+    }
+
+    // Now that all of the links are known to be good, the internal ones are rewritten into the
+    // TUI's routes
+    site.rewrite_links();
+    for (name, json) in site.json() {
+        write(&name, &json)?;
+    }
+    for (name, feed) in feeds::feeds(&site) {
+        write(&format!("feeds/{name}"), &feed)?;
+    }
+    for (name, text) in text::renditions(&site, theme) {
+        write(&format!("text/{name}"), &text)?;
+    }
+    for (name, gemtext) in gemtext::capsule(&site) {
+        write(&format!("gemini/{name}"), &gemtext)?;
+    }
+    for (name, gophermap) in gopher::hole(&site) {
+        write(&format!("gopher/{name}"), &gophermap)?;
     }
-    posts.sort_by(|prior, next| prior.0.cmp(&next.0));
-    let posts = posts
-        .into_iter()
-        .map(|(_, summary)| summary)
-        .collect::<Vec<_>>();
-    let json = serde_json::to_string(&posts).unwrap();
-    assets_path.push("posts.json");
-    std::fs::write(&assets_path, &json).unwrap();
-    assets_path.pop();
+    write(sitemap::SITEMAP, &sitemap::sitemap(&site))?;
+    write(sitemap::ROBOTS, &sitemap::robots(&site))?;
+    println!(
+        "Built {} posts for {} ({}) into {}",
+        site.posts.len(),
+        site.config.title,
+        site.config.base_url,
+        out.display()
+    );
+    Ok(site)
 }
@@ -0,0 +1,347 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        State, WebSocketUpgrade,
+    },
+    http::{header, StatusCode, Uri},
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+    Router,
+};
+use notify::{RecursiveMode, Watcher};
+use tokio::sync::broadcast;
+
+use crate::{
+    build,
+    config::SiteConfig,
+    diagnostics::Diagnostic,
+    html::{inject_meta, BASE_PATH_META},
+    Cli, ServeOptions,
+};
+
+/// The path of the websocket that pages are told to reload over.
+const LIVE_RELOAD: &str = "/__livereload";
+
+/// What changed, as told to the open pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reload {
+    /// The content changed, so the current page should refetch its JSON.
+    Content,
+    /// The frontend was rebuilt, so the page needs to be reloaded.
+    Frontend,
+}
+
+impl Reload {
+    fn message(self) -> &'static str {
+        match self {
+            Reload::Content => "content",
+            Reload::Frontend => "frontend",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ServeState {
+    out: PathBuf,
+    /// The path of the base URL, e.g. `/blog`, which is empty for most sites.
+    site_path: String,
+    reloads: broadcast::Sender<Reload>,
+}
+
+/// Builds the site and serves it on localhost with the same layout as the deployed site. The
+/// content directory and config are watched and the site is rebuilt whenever they change. The
+/// output directory is also watched for a new build of the frontend (e.g. from `trunk watch`).
+/// Any open pages are told about changes over a websocket.
+pub(crate) fn serve(cli: &Cli, options: &ServeOptions) -> Result<(), Vec<Diagnostic>> {
+    if let Err(errors) = build(cli, &options.build) {
+        report(&errors);
+    }
+    // The URLs follow the base URL that the site is built for, so changes to it need a restart
+    let config = SiteConfig::load(&cli.config).map_err(|err| vec![err])?;
+
+    let (reloads, _) = broadcast::channel(16);
+    let state = ServeState {
+        out: cli.out.clone(),
+        site_path: config.site_path().to_owned(),
+        reloads: reloads.clone(),
+    };
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let addr = (std::net::Ipv4Addr::LOCALHOST, options.port);
+    let listener = runtime
+        .block_on(tokio::net::TcpListener::bind(addr))
+        .map_err(|err| {
+            vec![Diagnostic::new(
+                &cli.out,
+                format!("could not listen on port {}: {err}", options.port),
+            )]
+        })?;
+    let app = Router::new()
+        .route(LIVE_RELOAD, get(live_reload))
+        .fallback(get(file))
+        .with_state(state);
+    runtime.spawn(async move { axum::serve(listener, app).await });
+    println!(
+        "Serving the TUI at http://localhost:{}{}/",
+        options.port,
+        config.base_path()
+    );
+
+    let (send, changes) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(send).unwrap();
+    let watched = [
+        (&cli.content, RecursiveMode::Recursive),
+        (&cli.config, RecursiveMode::NonRecursive),
+        (&cli.out, RecursiveMode::NonRecursive),
+    ];
+    for (path, mode) in watched {
+        watcher.watch(path, mode).map_err(|err| {
+            vec![Diagnostic::new(
+                path,
+                format!("could not watch for changes: {err}"),
+            )]
+        })?;
+    }
+
+    while let Ok(event) = changes.recv() {
+        // Editors tend to touch files several times when saving, so changes are gathered up until
+        // things settle down
+        let mut events = vec![event];
+        while let Ok(event) = changes.recv_timeout(Duration::from_millis(100)) {
+            events.push(event);
+        }
+        let paths: Vec<PathBuf> = events
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|event| !event.kind.is_access())
+            .flat_map(|event| event.paths)
+            .collect();
+        let Some(reload) = classify(cli, &paths) else {
+            continue;
+        };
+        if reload == Reload::Content {
+            if let Err(errors) = build(cli, &options.build) {
+                report(&errors);
+                continue;
+            }
+        }
+        // There might not be any pages open, which is fine
+        let _ = reloads.send(reload);
+    }
+    Ok(())
+}
+
+fn report(errors: &[Diagnostic]) {
+    for err in errors {
+        eprintln!("{err}");
+    }
+    eprintln!("The site was not rebuilt. Waiting for changes...");
+}
+
+/// Figures out what changed. The builder's own output is ignored.
+fn classify(cli: &Cli, paths: &[PathBuf]) -> Option<Reload> {
+    let is_content = |path: &PathBuf| path.starts_with(&cli.content) || path == &cli.config;
+    let is_frontend = |path: &PathBuf| {
+        path.starts_with(&cli.out)
+            && path
+                .extension()
+                .is_some_and(|ext| ext == "wasm" || ext == "js" || ext == "html")
+    };
+    if paths.iter().any(is_frontend) {
+        Some(Reload::Frontend)
+    } else if paths.iter().any(is_content) {
+        Some(Reload::Content)
+    } else {
+        None
+    }
+}
+
+async fn live_reload(State(state): State<ServeState>, ws: WebSocketUpgrade) -> Response {
+    let mut reloads = state.reloads.subscribe();
+    ws.on_upgrade(move |mut socket: WebSocket| async move {
+        while let Ok(reload) = reloads.recv().await {
+            if socket
+                .send(Message::Text(reload.message().into()))
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+    })
+}
+
+async fn file(State(state): State<ServeState>, uri: Uri) -> Response {
+    match resolve(&state.out, &state.site_path, uri.path()) {
+        Some(Resolved::Redirect(to)) => Redirect::temporary(&to).into_response(),
+        Some(Resolved::Index) => {
+            match tokio::fs::read_to_string(state.out.join("index.html")).await {
+                Ok(html) => (
+                    [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+                    index_page(&html, &state.site_path),
+                )
+                    .into_response(),
+                Err(_) => (
+                    StatusCode::NOT_FOUND,
+                    "The frontend has not been built. Run `trunk build` in `crates/frontend`.",
+                )
+                    .into_response(),
+            }
+        }
+        Some(Resolved::File(path)) => match tokio::fs::read(&path).await {
+            Ok(data) => ([(header::CONTENT_TYPE, content_type(&path))], data).into_response(),
+            Err(_) => StatusCode::NOT_FOUND.into_response(),
+        },
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// What is served at a URL.
+#[derive(Debug, PartialEq, Eq)]
+enum Resolved {
+    Redirect(String),
+    /// The frontend's `index.html`. The frontend routes everything under `/tui`.
+    Index,
+    File(PathBuf),
+}
+
+/// Maps a URL onto the output directory using the same layout as the deployed site: the frontend
+/// and the badge are at the root, the JSON for each page is under `/tui`, the JSON for each post
+/// is under `/tui/posts`, and the feeds are under `/tui`. All of these are under the path of the
+/// base URL (`site_path`), if it has one.
+fn resolve(out: &Path, site_path: &str, path: &str) -> Option<Resolved> {
+    if path
+        .split('/')
+        .any(|segment| segment == ".." || segment.contains('\\'))
+    {
+        return None;
+    }
+    if path == "/" || path == site_path || path == format!("{site_path}/") {
+        return Some(Resolved::Redirect(format!("{site_path}/tui/")));
+    }
+    let path = path
+        .strip_prefix(site_path)
+        .filter(|path| path.starts_with('/'))?;
+    if let Some(rest) = path.strip_prefix("/tui") {
+        let rest = rest.trim_start_matches('/');
+        if rest.ends_with(".xml") || rest == "feed.json" || rest.ends_with("/feed.json") {
+            return Some(Resolved::File(out.join("feeds").join(rest)));
+        }
+        if let Some(name) = rest.strip_prefix("blog/") {
+            if !name.contains('/') && (name.ends_with(".ans") || name.ends_with(".txt")) {
+                return Some(Resolved::File(out.join("text").join(name)));
+            }
+        }
+        let json = match rest.strip_prefix("posts/") {
+            Some(post) => post
+                .strip_suffix(".json")
+                .map(|name| (name, out.join("posts"))),
+            None => rest
+                .strip_suffix(".json")
+                .map(|name| (name, out.to_owned())),
+        };
+        return Some(match json {
+            Some((name, dir)) if !name.contains('/') => {
+                Resolved::File(dir.join(format!("{name}.json")))
+            }
+            _ => Resolved::Index,
+        });
+    }
+    let file = path.trim_start_matches('/');
+    (!file.is_empty() && !file.contains('/')).then(|| Resolved::File(out.join(file)))
+}
+
+/// Tells the frontend the path that it is served under (like the deployed site does) and marks
+/// the page as being served locally so that the frontend connects to the live reload websocket.
+fn index_page(html: &str, site_path: &str) -> String {
+    let html = inject_meta(html, BASE_PATH_META, &format!("{site_path}/tui"));
+    inject_meta(&html, "live-reload", LIVE_RELOAD)
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => "application/json",
+        Some("js") => "text/javascript",
+        Some("wasm") => "application/wasm",
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css",
+        Some("xml") => "application/xml",
+        Some("ans" | "txt") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{index_page, Resolved};
+
+    #[test]
+    fn urls_resolve_like_the_deployed_site() {
+        let out = Path::new("out");
+        let file = |name: &str| Some(Resolved::File(out.join(name)));
+        // The site is at the root of its domain
+        let resolve = |path: &str| super::resolve(out, "", path);
+        assert_eq!(resolve("/"), Some(Resolved::Redirect("/tui/".to_owned())));
+        assert_eq!(resolve("/tui"), Some(Resolved::Index));
+        assert_eq!(resolve("/tui/blog/some-post"), Some(Resolved::Index));
+        assert_eq!(resolve("/tui/home.json"), file("home.json"));
+        assert_eq!(resolve("/tui/posts-1.json"), file("posts-1.json"));
+        assert_eq!(
+            resolve("/tui/posts/some-post.json"),
+            file("posts/some-post.json")
+        );
+        assert_eq!(
+            resolve("/avid-rustacean-frontend_bg.wasm"),
+            file("avid-rustacean-frontend_bg.wasm")
+        );
+        assert_eq!(resolve("/tui/atom.xml"), file("feeds/atom.xml"));
+        assert_eq!(
+            resolve("/tui/tags/rust/feed.json"),
+            file("feeds/tags/rust/feed.json")
+        );
+        assert_eq!(
+            resolve("/tui/blog/some-post.ans"),
+            file("text/some-post.ans")
+        );
+        assert_eq!(
+            resolve("/tui/blog/some-post.txt"),
+            file("text/some-post.txt")
+        );
+        assert_eq!(resolve("/tui/posts/../../secret.json"), None);
+        assert_eq!(resolve("/some/nested/file.txt"), None);
+
+        // Everything moves under the path of the base URL
+        let resolve = |path: &str| super::resolve(out, "/blog", path);
+        assert_eq!(
+            resolve("/"),
+            Some(Resolved::Redirect("/blog/tui/".to_owned()))
+        );
+        assert_eq!(
+            resolve("/blog"),
+            Some(Resolved::Redirect("/blog/tui/".to_owned()))
+        );
+        assert_eq!(resolve("/blog/tui/archive"), Some(Resolved::Index));
+        assert_eq!(resolve("/blog/tui/home.json"), file("home.json"));
+        assert_eq!(
+            resolve("/blog/avid-rustacean-frontend.js"),
+            file("avid-rustacean-frontend.js")
+        );
+        assert_eq!(resolve("/tui/home.json"), None);
+    }
+
+    #[test]
+    fn live_reload_is_injected() {
+        let html = "<html><head><title>Hi</title></head></html>";
+        assert_eq!(
+            index_page(html, ""),
+            r#"<html><head><meta name="live-reload" content="/__livereload"><meta name="tui-base" content="/tui"><title>Hi</title></head></html>"#
+        );
+    }
+}
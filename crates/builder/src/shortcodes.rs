@@ -0,0 +1,499 @@
+use std::collections::{BTreeMap, HashMap};
+
+use avid_rustacean_model::{Markdown, MdNode};
+
+/// Turns a shortcode into the markdown nodes that it stands for, or explains what is wrong with
+/// it.
+pub(crate) type Handler = fn(&Shortcode<'_>) -> Result<Vec<MdNode>, String>;
+
+/// Marks where a shortcode was while the rest of the page is parsed. The character is from the
+/// private use area, so it won't show up in a page by accident.
+const PLACEHOLDER: char = '\u{E000}';
+
+/// A use of a shortcode in a page. Zola has two kinds: `{{ name(args) }}`, and
+/// `{% name(args) %}body{% end %}`, which also has a body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Shortcode<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) args: BTreeMap<&'a str, String>,
+    pub(crate) body: Option<&'a str>,
+}
+
+impl Shortcode<'_> {
+    /// An argument that has to be given.
+    pub(crate) fn arg(&self, name: &str) -> Result<&str, String> {
+        self.args
+            .get(name)
+            .map(String::as_str)
+            .ok_or_else(|| format!("`{}` needs a `{name}` argument", self.name))
+    }
+
+    fn body(&self) -> Result<&str, String> {
+        self.body.ok_or_else(|| {
+            format!(
+                "`{0}` needs a body: `{{% {0}(...) %}}...{{% end %}}`",
+                self.name
+            )
+        })
+    }
+}
+
+/// A problem with a shortcode. The line is relative to the start of the markdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ShortcodeError {
+    pub(crate) line: usize,
+    pub(crate) message: String,
+}
+
+/// The shortcodes that pages can use. Zola renders shortcodes with templates, but the TUI doesn't
+/// have those, so each one is handled in Rust instead.
+#[derive(Debug, Clone)]
+pub(crate) struct Shortcodes {
+    handlers: HashMap<&'static str, Handler>,
+}
+
+impl Default for Shortcodes {
+    /// The built-in shortcodes.
+    fn default() -> Self {
+        let mut digest = Self {
+            handlers: HashMap::new(),
+        };
+        digest.register("youtube", youtube);
+        digest.register("vimeo", vimeo);
+        digest.register("note", note);
+        digest.register("gist", gist);
+        digest
+    }
+}
+
+impl Shortcodes {
+    pub(crate) fn register(&mut self, name: &'static str, handler: Handler) {
+        self.handlers.insert(name, handler);
+    }
+
+    /// Parses a page, expanding every shortcode in it. Shortcodes in code are left alone.
+    pub(crate) fn parse(&self, md: &str) -> Result<Markdown, ShortcodeParseError> {
+        let (md, expansions) = self.extract(md).map_err(ShortcodeParseError::Shortcodes)?;
+        let mut md: Markdown = md.parse().map_err(ShortcodeParseError::Markdown)?;
+        if !expansions.is_empty() {
+            md.0 = splice(std::mem::take(&mut md.0), &expansions);
+            let errors = stranded(&md.0, &expansions);
+            if !errors.is_empty() {
+                return Err(ShortcodeParseError::Shortcodes(errors));
+            }
+        }
+        Ok(md)
+    }
+
+    /// Swaps every shortcode for a placeholder and runs their handlers. The placeholders are
+    /// followed by as many newlines as the shortcodes took up so that the lines of the rest of the
+    /// page don't move.
+    fn extract(&self, md: &str) -> Result<(String, Vec<Expansion>), Vec<ShortcodeError>> {
+        let mut digest = String::with_capacity(md.len());
+        let mut expansions = Vec::new();
+        let mut errors = Vec::new();
+        let (mut i, mut line) = (0, 1);
+        let (mut fence, mut code, mut line_start) = (false, false, true);
+        while let Some(c) = md[i..].chars().next() {
+            let rest = &md[i..];
+            if line_start {
+                let trimmed = rest.trim_start_matches([' ', '\t']);
+                if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                    fence = !fence;
+                }
+            }
+            if !fence && !code && (rest.starts_with("{{") || rest.starts_with("{%")) {
+                if let Some((len, shortcode)) = find_shortcode(rest) {
+                    let expansion = shortcode.and_then(|shortcode| {
+                        let handler = self
+                            .handlers
+                            .get(shortcode.name)
+                            .ok_or_else(|| format!("unknown shortcode `{}`", shortcode.name))?;
+                        handler(&shortcode)
+                    });
+                    match expansion {
+                        Ok(nodes) => {
+                            digest.push(PLACEHOLDER);
+                            digest.push_str(&expansions.len().to_string());
+                            digest.push(PLACEHOLDER);
+                            expansions.push(Expansion { line, nodes });
+                        }
+                        Err(message) => errors.push(ShortcodeError { line, message }),
+                    }
+                    let newlines = rest[..len].matches('\n').count();
+                    digest.extend(std::iter::repeat_n('\n', newlines));
+                    line += newlines;
+                    i += len;
+                    line_start = false;
+                    continue;
+                }
+            }
+            match c {
+                '`' => code = !code,
+                '\n' => {
+                    line += 1;
+                    code = false;
+                }
+                _ => {}
+            }
+            line_start = c == '\n';
+            digest.push(c);
+            i += c.len_utf8();
+        }
+        match errors.is_empty() {
+            true => Ok((digest, expansions)),
+            false => Err(errors),
+        }
+    }
+}
+
+/// The nodes that a shortcode expanded into, and the line that it was on.
+#[derive(Debug)]
+struct Expansion {
+    line: usize,
+    nodes: Vec<MdNode>,
+}
+
+/// Why a page couldn't be parsed.
+#[derive(Debug)]
+pub(crate) enum ShortcodeParseError {
+    Shortcodes(Vec<ShortcodeError>),
+    Markdown(avid_rustacean_model::MdParseError),
+}
+
+/// Finds the shortcode at the start of the text, returning how long it is. Text that doesn't look
+/// like a shortcode call (e.g. `{{ x }}` in prose) is left alone.
+fn find_shortcode(text: &str) -> Option<(usize, Result<Shortcode<'_>, String>)> {
+    let close = if text.starts_with("{{") { "}}" } else { "%}" };
+    // The tag itself has to be on one line
+    let line = text.split('\n').next().unwrap_or_default();
+    // The close has to come after the open, which it doesn't in e.g. `{%}`
+    let end = line.find(close).filter(|&end| end >= 2)?;
+    let (name, args) = parse_call(text[2..end].trim())?;
+    let tag_len = end + close.len();
+    if close == "}}" {
+        let shortcode = args.map(|args| Shortcode {
+            name,
+            args,
+            body: None,
+        });
+        return Some((tag_len, shortcode));
+    }
+
+    // Block shortcodes run until the next `{% end %}`
+    let rest = &text[tag_len..];
+    let mut search = 0;
+    while let Some(start) = rest[search..].find("{%").map(|i| i + search) {
+        let Some(end) = rest[start..].find("%}").map(|i| i + start) else {
+            break;
+        };
+        if rest[start + 2..end].trim() == "end" {
+            let shortcode = args.map(|args| Shortcode {
+                name,
+                args,
+                body: Some(&rest[..start]),
+            });
+            return Some((tag_len + end + "%}".len(), shortcode));
+        }
+        search = end;
+    }
+    Some((
+        tag_len,
+        Err(format!("`{name}` is never closed with `{{% end %}}`")),
+    ))
+}
+
+/// Parses a call like `name(key="value", n=1)`. This returns `None` if the text isn't a call at
+/// all, and an error if the arguments are malformed.
+#[allow(clippy::type_complexity)]
+fn parse_call(text: &str) -> Option<(&str, Result<BTreeMap<&str, String>, String>)> {
+    let (name, args) = text.split_once('(')?;
+    let name = name.trim();
+    let args = args.trim_end().strip_suffix(')')?;
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+    Some((
+        name,
+        parse_args(args).map_err(|err| format!("`{name}`: {err}")),
+    ))
+}
+
+fn parse_args(mut text: &str) -> Result<BTreeMap<&str, String>, String> {
+    let mut args = BTreeMap::new();
+    loop {
+        text = text.trim_start();
+        if text.is_empty() {
+            return Ok(args);
+        }
+        let (key, rest) = text
+            .split_once('=')
+            .ok_or_else(|| format!("expected `key=value`, found `{text}`"))?;
+        let key = key.trim();
+        let rest = rest.trim_start();
+        let (value, rest) = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let end = rest[1..]
+                    .find(quote)
+                    .ok_or_else(|| format!("the value of `{key}` is missing a closing {quote}"))?;
+                (rest[1..end + 1].to_owned(), &rest[end + 2..])
+            }
+            _ => {
+                let end = rest.find(',').unwrap_or(rest.len());
+                (rest[..end].trim().to_owned(), &rest[end..])
+            }
+        };
+        args.insert(key, value);
+        text = rest.trim_start();
+        text = match text.strip_prefix(',') {
+            Some(text) => text,
+            None if text.is_empty() => text,
+            None => return Err(format!("expected `,` after the value of `{key}`")),
+        };
+    }
+}
+
+/// Puts the expanded shortcodes in place of their placeholders. A shortcode in the middle of a
+/// paragraph splits it in two. Only the text of paragraphs (including those in lists) can hold
+/// shortcodes; the placeholders anywhere else are left for [`stranded`] to find.
+fn splice(nodes: Vec<MdNode>, expansions: &[Expansion]) -> Vec<MdNode> {
+    let mut digest = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        let children = match node {
+            MdNode::Paragraph(children) => children,
+            MdNode::List(items) => {
+                digest.push(MdNode::List(splice(items, expansions)));
+                continue;
+            }
+            node => {
+                digest.push(node);
+                continue;
+            }
+        };
+        let mut paragraph = Vec::new();
+        for child in children {
+            let MdNode::Text(text) = child else {
+                paragraph.push(child);
+                continue;
+            };
+            for (i, piece) in text.split(PLACEHOLDER).enumerate() {
+                // The pieces alternate between text and the index of a shortcode
+                if i % 2 == 0 {
+                    if !piece.trim().is_empty() {
+                        paragraph.push(MdNode::Text(piece.to_owned()));
+                    }
+                    continue;
+                }
+                let expansion = piece.parse().ok().and_then(|i: usize| expansions.get(i));
+                if let Some(expansion) = expansion {
+                    if !paragraph.is_empty() {
+                        digest.push(MdNode::Paragraph(std::mem::take(&mut paragraph)));
+                    }
+                    digest.extend(expansion.nodes.iter().cloned());
+                }
+            }
+        }
+        if !paragraph.is_empty() {
+            digest.push(MdNode::Paragraph(paragraph));
+        }
+    }
+    digest
+}
+
+/// Finds the shortcodes that were used where they can't be spliced in, like in a heading or the
+/// text of a link.
+fn stranded(nodes: &[MdNode], expansions: &[Expansion]) -> Vec<ShortcodeError> {
+    let mut digest = Vec::new();
+    for node in nodes {
+        let text = match node {
+            MdNode::Paragraph(nodes) | MdNode::List(nodes) => {
+                digest.extend(stranded(nodes, expansions));
+                continue;
+            }
+            MdNode::Link(text, url) => vec![text, url],
+            MdNode::BlockQuote(text)
+            | MdNode::InlineCode(text)
+            | MdNode::Emphasis(text)
+            | MdNode::Strong(text)
+            | MdNode::Heading(text)
+            | MdNode::Text(text) => vec![text],
+            MdNode::Code(_) | MdNode::ThematicBreak | MdNode::Break => continue,
+        };
+        // As in `splice`, every other piece is the index of a shortcode
+        let indices = text.into_iter().flat_map(|text| {
+            text.split(PLACEHOLDER)
+                .skip(1)
+                .step_by(2)
+                .filter_map(|i| i.parse::<usize>().ok())
+        });
+        digest.extend(
+            indices
+                .filter_map(|i| expansions.get(i))
+                .map(|expansion| ShortcodeError {
+                    line: expansion.line,
+                    message: "shortcodes can only be used in the text of a paragraph".to_owned(),
+                }),
+        );
+    }
+    digest
+}
+
+/// A card that links to a video, since the TUI can't embed one.
+fn video_card(title: Option<&String>, site: &str, url: String) -> Vec<MdNode> {
+    let title = title.map_or_else(|| format!("{site} video"), Clone::clone);
+    vec![MdNode::Paragraph(vec![
+        MdNode::Strong(format!("▶ {title}")),
+        MdNode::Text(" — ".to_owned()),
+        MdNode::Link(format!("Watch on {site}"), url),
+    ])]
+}
+
+/// `{{ youtube(id="...", title="...") }}`, which matches Zola's built-in shortcode (plus a title).
+fn youtube(shortcode: &Shortcode<'_>) -> Result<Vec<MdNode>, String> {
+    let url = format!("https://www.youtube.com/watch?v={}", shortcode.arg("id")?);
+    Ok(video_card(shortcode.args.get("title"), "YouTube", url))
+}
+
+/// `{{ vimeo(id="...", title="...") }}`
+fn vimeo(shortcode: &Shortcode<'_>) -> Result<Vec<MdNode>, String> {
+    let url = format!("https://vimeo.com/{}", shortcode.arg("id")?);
+    Ok(video_card(shortcode.args.get("title"), "Vimeo", url))
+}
+
+/// `{% note(title="...") %}...{% end %}`, a box that is set apart from the text around it.
+fn note(shortcode: &Shortcode<'_>) -> Result<Vec<MdNode>, String> {
+    let title = shortcode.args.get("title").map_or("Note", String::as_str);
+    let body = shortcode.body()?.split_whitespace().collect::<Vec<_>>();
+    Ok(vec![MdNode::BlockQuote(format!(
+        "{title}: {}",
+        body.join(" ")
+    ))])
+}
+
+/// `{{ gist(url="...", file="...") }}`. Gists can't be embedded in the TUI, so this links to it.
+fn gist(shortcode: &Shortcode<'_>) -> Result<Vec<MdNode>, String> {
+    let url = shortcode.arg("url")?;
+    let mut name = url
+        .trim_start_matches("https://gist.github.com/")
+        .to_owned();
+    if let Some(file) = shortcode.args.get("file") {
+        name = format!("{name} ({file})");
+    }
+    Ok(vec![MdNode::Paragraph(vec![
+        MdNode::Strong("Gist: ".to_owned()),
+        MdNode::Link(name, url.to_owned()),
+    ])])
+}
+
+#[cfg(test)]
+mod tests {
+    use avid_rustacean_model::MdNode;
+
+    use super::{splice, Expansion, ShortcodeParseError, Shortcodes, PLACEHOLDER};
+
+    fn errors(md: &str) -> Vec<(usize, String)> {
+        match Shortcodes::default().parse(md) {
+            Err(ShortcodeParseError::Shortcodes(errors)) => errors
+                .into_iter()
+                .map(|err| (err.line, err.message))
+                .collect(),
+            other => panic!("expected shortcode errors, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn builtin_shortcodes() {
+        let md = Shortcodes::default()
+            .parse(
+                "Before\n{{ youtube(id=\"abc\", title='A talk') }}\nafter\n\n\
+                 {% note() %}\nSome *words*\nhere\n{% end %}\n\n\
+                 {{ gist(url=\"https://gist.github.com/someone/123\") }}",
+            )
+            .unwrap();
+        assert_eq!(
+            md.0,
+            [
+                MdNode::Paragraph(vec![MdNode::Text("Before\n".to_owned())]),
+                MdNode::Paragraph(vec![
+                    MdNode::Strong("▶ A talk".to_owned()),
+                    MdNode::Text(" — ".to_owned()),
+                    MdNode::Link(
+                        "Watch on YouTube".to_owned(),
+                        "https://www.youtube.com/watch?v=abc".to_owned()
+                    ),
+                ]),
+                MdNode::Paragraph(vec![MdNode::Text("\nafter".to_owned())]),
+                MdNode::BlockQuote("Note: Some *words* here".to_owned()),
+                MdNode::Paragraph(vec![
+                    MdNode::Strong("Gist: ".to_owned()),
+                    MdNode::Link(
+                        "someone/123".to_owned(),
+                        "https://gist.github.com/someone/123".to_owned()
+                    ),
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn code_is_left_alone() {
+        let md = "`{{ nope() }}`\n\n```rust\nlet s = format!(\"{{ nope() }}\");\n```\n\n{{ x }} {%} {%%}";
+        let parsed = Shortcodes::default().parse(md).unwrap();
+        assert_eq!(parsed, md.parse().unwrap());
+    }
+
+    #[test]
+    fn bad_shortcodes_are_errors() {
+        assert_eq!(
+            errors("Hi\n\n{{ nope(a=1) }}\n\n{% note(title=\"x\") %}\nnever closed"),
+            [
+                (3, "unknown shortcode `nope`".to_owned()),
+                (5, "`note` is never closed with `{% end %}`".to_owned()),
+            ]
+        );
+        assert_eq!(
+            errors("{{ youtube() }}\n{{ note() }}\n{{ gist(url=\"x\" file=\"y\") }}"),
+            [
+                (1, "`youtube` needs a `id` argument".to_owned()),
+                (
+                    2,
+                    "`note` needs a body: `{% note(...) %}...{% end %}`".to_owned()
+                ),
+                (
+                    3,
+                    "`gist`: expected `,` after the value of `url`".to_owned()
+                ),
+            ]
+        );
+        // Lines after a block shortcode stay where they were
+        assert_eq!(
+            errors("{% note() %}\na\nb\n{% end %}\n{{ nope() }}"),
+            [(5, "unknown shortcode `nope`".to_owned())]
+        );
+        // Shortcodes that can't be spliced in aren't dropped without a word
+        let stranded = "shortcodes can only be used in the text of a paragraph".to_owned();
+        assert_eq!(
+            errors("# {{ vimeo(id=1) }}\n\n*a {{ vimeo(id=2) }}*\n\n[{{ vimeo(id=3) }}](/)"),
+            [(1, stranded.clone()), (3, stranded.clone()), (5, stranded)]
+        );
+    }
+
+    #[test]
+    fn lists_are_spliced() {
+        let placeholder = |i: usize| format!("{PLACEHOLDER}{i}{PLACEHOLDER}");
+        let nodes = vec![MdNode::List(vec![MdNode::Paragraph(vec![MdNode::Text(
+            format!("a {}", placeholder(0)),
+        )])])];
+        let expansions = [Expansion {
+            line: 1,
+            nodes: vec![MdNode::ThematicBreak],
+        }];
+        assert_eq!(
+            splice(nodes, &expansions),
+            [MdNode::List(vec![
+                MdNode::Paragraph(vec![MdNode::Text("a ".to_owned())]),
+                MdNode::ThematicBreak,
+            ])]
+        );
+    }
+}
@@ -0,0 +1,791 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use avid_rustacean_model::{
+    post_slug, split_markdown, Aliases, Archive, HomePage, Markdown, MdParseError, Post, PostPage,
+    PostSummary, SearchIndex,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use toml::value::Datetime;
+
+use crate::{
+    cache::BuildCache,
+    config::SiteConfig,
+    diagnostics::Diagnostic,
+    feeds::timestamp,
+    links::{check_external, LinkChecker, LinkError, LINK_TIMEOUT},
+    shortcodes::{ShortcodeParseError, Shortcodes},
+};
+
+/// The path of the home page, relative to the content directory.
+pub(crate) const HOME_PAGE: &str = "pages/about/index.md";
+/// The path of the projects page, relative to the content directory.
+pub(crate) const PROJECTS_PAGE: &str = "pages/projects.md";
+/// The path of the blog's section page, relative to the content directory.
+pub(crate) const BLOG_INDEX: &str = "_index.md";
+
+/// All of the parsed content of the site.
+#[derive(Debug)]
+pub(crate) struct Site {
+    pub(crate) config: SiteConfig,
+    /// The directory that the content was read from.
+    pub(crate) content: PathBuf,
+    pub(crate) home: HomePage,
+    pub(crate) projects: Markdown,
+    /// Every post, sorted from oldest to newest.
+    pub(crate) posts: Vec<Post>,
+    /// How many posts are on each page of the blog index. Without this, every post is on one page.
+    pub(crate) paginate_by: Option<usize>,
+}
+
+/// The front matter of the blog's section page. Zola supports much more, but this is all that the
+/// builder uses.
+#[derive(Debug, Default, Deserialize)]
+struct SectionFrontMatter {
+    paginate_by: Option<usize>,
+}
+
+/// The front matter of a post. Everything is optional here so that missing fields can be
+/// reported by name.
+#[derive(Debug, Deserialize)]
+struct FrontMatter {
+    title: Option<String>,
+    date: Option<Datetime>,
+    /// When the post was last edited, if it has been.
+    updated: Option<Datetime>,
+    description: Option<String>,
+    #[serde(default)]
+    taxonomies: Taxonomies,
+    /// The old paths of the post, like Zola's `aliases`. Each one is redirected to the post.
+    #[serde(default)]
+    aliases: Vec<String>,
+}
+
+/// The taxonomies that a post is filed under. These follow Zola's `[taxonomies]` table.
+#[derive(Debug, Default, Deserialize)]
+struct Taxonomies {
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl Site {
+    /// Reads and parses the config and everything in a Zola content directory. Each markdown file
+    /// at the top of the directory (other than `_index.md`) is a post. Every problem that is found
+    /// is reported, not just the first.
+    pub(crate) fn load(
+        content: &Path,
+        config: &Path,
+        cache: &mut BuildCache,
+    ) -> Result<Self, Vec<Diagnostic>> {
+        let mut errors = Vec::new();
+        let config = SiteConfig::load(config)
+            .map_err(|err| errors.push(err))
+            .ok();
+        let home = load_page(&content.join(HOME_PAGE), cache)
+            .map_err(|errs| errors.extend(errs))
+            .ok();
+        let projects = load_page(&content.join(PROJECTS_PAGE), cache)
+            .map_err(|errs| errors.extend(errs))
+            .ok();
+        let section = load_section(&content.join(BLOG_INDEX))
+            .map_err(|err| errors.push(err))
+            .ok();
+
+        let mut posts = Vec::new();
+        let mut names = HashMap::new();
+        match post_paths(content) {
+            Ok(paths) => {
+                for path in paths {
+                    match load_post(&path, cache) {
+                        Ok(post) => {
+                            errors.extend(claim_names(&mut names, &path, &post));
+                            posts.push(post);
+                        }
+                        Err(errs) => errors.extend(errs),
+                    }
+                }
+            }
+            Err(err) => errors.push(err),
+        }
+        posts.sort_by_cached_key(|post| post.summary.create_on.parse::<Datetime>().ok());
+
+        match (config, home, projects, section) {
+            (Some(config), Some(home), Some(projects), Some(section)) if errors.is_empty() => {
+                Ok(Self {
+                    config,
+                    content: content.to_owned(),
+                    home: HomePage { body: home },
+                    projects,
+                    posts,
+                    paginate_by: section.paginate_by,
+                })
+            }
+            _ => Err(errors),
+        }
+    }
+
+    /// The path of a page's markdown file. Posts are found by their slug, since that might not
+    /// match the name of their file.
+    pub(crate) fn page_path(&self, page: &str) -> PathBuf {
+        if page.ends_with(".md") {
+            return self.content.join(page);
+        }
+        post_paths(&self.content)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|path| {
+                path.file_stem()
+                    .is_some_and(|stem| post_slug(&stem.to_string_lossy()) == page)
+            })
+            .unwrap_or_else(|| self.content.join(format!("{page}.md")))
+    }
+
+    /// Every page of the site and its name, for reporting errors.
+    fn pages(&self) -> Vec<(&str, &Markdown)> {
+        [
+            (HOME_PAGE, &self.home.body),
+            (PROJECTS_PAGE, &self.projects),
+        ]
+        .into_iter()
+        .chain(self.posts.iter().flat_map(|post| {
+            let name = post.summary.real_name.as_str();
+            [(name, &post.body), (name, &post.summary.summary)]
+        }))
+        .collect()
+    }
+
+    /// The pages of the blog index, newest post first. There is always at least one page, even if
+    /// there are no posts.
+    pub(crate) fn post_pages(&self) -> Vec<PostPage> {
+        let summaries: Vec<PostSummary> = self
+            .posts
+            .iter()
+            .rev()
+            .map(|post| post.summary.clone())
+            .collect();
+        let per_page = self.paginate_by.unwrap_or(summaries.len()).max(1);
+        let pages = summaries.len().div_ceil(per_page).max(1);
+        let mut chunks = summaries.chunks(per_page);
+        (1..=pages)
+            .map(|page| PostPage {
+                page,
+                pages,
+                posts: chunks.next().unwrap_or_default().to_vec(),
+            })
+            .collect()
+    }
+
+    /// Every route of the frontend and when its content last changed, if that is known. The paths
+    /// are relative to the root of the site.
+    pub(crate) fn routes(&self) -> Vec<Route> {
+        let newest = self.posts.iter().map(updated).max();
+        let mut routes = vec![
+            Route::new("tui", newest.clone()),
+            Route::new("tui/projects", None),
+            Route::new("tui/blog", newest.clone()),
+            Route::new("tui/archive", newest),
+        ];
+        routes.extend(self.posts.iter().map(|post| {
+            Route::new(
+                format!("tui/blog/{}", post.summary.real_name),
+                Some(updated(post)),
+            )
+        }));
+        routes
+    }
+
+    /// Maps the slug of every alias to the post that it leads to. The frontend uses this to follow
+    /// old links to posts. An alias whose slug is the post's own name (like the file name of a post
+    /// that isn't already a slug) is kept as it was written instead.
+    pub(crate) fn aliases(&self) -> Aliases {
+        self.posts
+            .iter()
+            .flat_map(|post| {
+                let name = &post.summary.real_name;
+                post.summary
+                    .aliases
+                    .iter()
+                    .map(|alias| match post_slug(alias) {
+                        slug if slug == *name => (alias.clone(), name.clone()),
+                        slug => (slug, name.clone()),
+                    })
+                    .filter(move |(alias, _)| alias != name)
+            })
+            .collect()
+    }
+
+    /// The old routes of posts, relative to the root of the site, and the URLs that they redirect
+    /// to. Each alias gets a route as it was written and as its slug, since old links could use
+    /// either.
+    pub(crate) fn redirects(&self) -> Vec<(String, String)> {
+        let mut digest: Vec<_> = self
+            .posts
+            .iter()
+            .flat_map(|post| {
+                let name = &post.summary.real_name;
+                let url = format!(
+                    "{}{}/blog/{name}",
+                    self.config.origin(),
+                    self.config.base_path()
+                );
+                post.summary
+                    .aliases
+                    .iter()
+                    .flat_map(|alias| [alias.clone(), post_slug(alias)])
+                    // The post's own route is its page, not a redirect
+                    .filter(move |alias| alias != name)
+                    .map(move |alias| (format!("tui/blog/{alias}"), url.clone()))
+            })
+            .collect();
+        digest.sort();
+        digest.dedup();
+        digest
+    }
+
+    fn link_checker(&self) -> LinkChecker {
+        LinkChecker::new(
+            self.posts
+                .iter()
+                .map(|post| (post.summary.real_name.as_str(), &post.body)),
+            &self.content,
+            &self.config.base_path(),
+        )
+    }
+
+    /// Checks all of the links in the site. External links are only checked if asked for.
+    pub(crate) fn check_links(
+        &self,
+        check_external_links: bool,
+        endpoint: Option<&str>,
+    ) -> Vec<Diagnostic> {
+        let pages = self.pages();
+        let checker = self.link_checker();
+        let mut errors: Vec<_> = pages
+            .iter()
+            .flat_map(|(page, md)| checker.check(page, md))
+            .collect();
+        if check_external_links {
+            let links = pages
+                .iter()
+                .flat_map(|(page, md)| md.links().into_iter().map(|(_, url)| (*page, url)));
+            errors.extend(check_external(links, endpoint, LINK_TIMEOUT));
+        }
+        errors
+            .into_iter()
+            .map(|err| self.link_diagnostic(err))
+            .collect()
+    }
+
+    /// Points a broken link at the line of the page that it is on.
+    fn link_diagnostic(&self, err: LinkError) -> Diagnostic {
+        let path = self.page_path(&err.page);
+        let digest = Diagnostic::new(&path, format!("broken link `{}`: {}", err.url, err.reason));
+        let source = std::fs::read_to_string(&path).unwrap_or_default();
+        match source.lines().position(|line| line.contains(&err.url)) {
+            Some(i) => digest.at_line(&source, i + 1),
+            None => digest,
+        }
+    }
+
+    /// Rewrites the internal links of every page into the TUI's routes.
+    pub(crate) fn rewrite_links(&mut self) {
+        let checker = self.link_checker();
+        checker.rewrite(HOME_PAGE, &mut self.home.body);
+        checker.rewrite(PROJECTS_PAGE, &mut self.projects);
+        for post in &mut self.posts {
+            checker.rewrite(&post.summary.real_name, &mut post.body);
+            checker.rewrite(&post.summary.real_name, &mut post.summary.summary);
+        }
+    }
+
+    /// The JSON for every page, along with the name of the file that it goes in.
+    pub(crate) fn json(&self) -> Vec<(String, String)> {
+        let mut digest = vec![
+            ("site.json".to_owned(), to_json(&self.config.site_info())),
+            ("home.json".to_owned(), to_json(&self.home)),
+            ("projects.json".to_owned(), to_json(&self.projects)),
+        ];
+        for post in &self.posts {
+            // Posts go in their own directory so that their names can't collide with the other files
+            digest.push((
+                format!("posts/{}.json", post.summary.real_name),
+                to_json(post),
+            ));
+        }
+        for page in self.post_pages() {
+            digest.push((format!("posts-{}.json", page.page), to_json(&page)));
+        }
+        digest.push((
+            "archive.json".to_owned(),
+            to_json(&Archive::new(&self.posts)),
+        ));
+        digest.push(("aliases.json".to_owned(), to_json(&self.aliases())));
+        digest.push((
+            "search.json".to_owned(),
+            to_json(&SearchIndex::new(&self.posts)),
+        ));
+        digest
+    }
+}
+
+/// A route of the frontend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Route {
+    /// The path of the route relative to the root of the site, e.g. `tui/blog`.
+    pub(crate) path: String,
+    /// When the content of the route last changed, as an RFC 3339 timestamp.
+    pub(crate) updated: Option<String>,
+}
+
+impl Route {
+    fn new(path: impl Into<String>, updated: Option<String>) -> Self {
+        Self {
+            path: path.into(),
+            updated,
+        }
+    }
+}
+
+/// When a post was last changed, as an RFC 3339 timestamp.
+pub(crate) fn updated(post: &Post) -> String {
+    match post.summary.last_edit {
+        Some(edit) => edit.to_rfc3339(),
+        None => timestamp(&post.summary.create_on),
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    // All of the models are plain data, so serializing them can not fail
+    serde_json::to_string(value).unwrap()
+}
+
+/// A markdown file that has been split into its front matter and body.
+struct MdFile {
+    front_matter: String,
+    body: String,
+    /// The number of lines before the body.
+    offset: usize,
+}
+
+fn read_source(path: &Path) -> Result<String, Diagnostic> {
+    std::fs::read_to_string(path)
+        .map_err(|err| Diagnostic::new(path, format!("could not read the file: {err}")))
+}
+
+/// Splits the front matter off of a markdown file.
+fn split_file(path: &Path, source: &str) -> Result<MdFile, Diagnostic> {
+    let (front_matter, body) =
+        split_markdown(source).map_err(|err| md_diagnostic(path, source, 0, err))?;
+    // The body starts after the opening `+++`, the front matter, and the closing `+++`
+    let offset = source
+        .lines()
+        .skip(1)
+        .position(|line| line == "+++")
+        .map_or(0, |i| i + 2);
+    Ok(MdFile {
+        front_matter,
+        body,
+        offset,
+    })
+}
+
+/// Reads the front matter of the blog's section page. The page is optional, like it is for Zola.
+fn load_section(path: &Path) -> Result<SectionFrontMatter, Diagnostic> {
+    if !path.exists() {
+        return Ok(SectionFrontMatter::default());
+    }
+    let source = read_source(path)?;
+    let file = split_file(path, &source)?;
+    toml::from_str(&file.front_matter)
+        .map_err(|err| Diagnostic::from_toml(path, &source, &file.front_matter, 2, &err))
+}
+
+/// Reads and parses a page without any front matter that the builder cares about. Unchanged pages
+/// are taken from the cache.
+fn load_page(path: &Path, cache: &mut BuildCache) -> Result<Markdown, Vec<Diagnostic>> {
+    let source = read_source(path).map_err(|err| vec![err])?;
+    cache.page(path, &source, || {
+        let file = split_file(path, &source).map_err(|err| vec![err])?;
+        parse_body(path, &source, &file.body, file.offset)
+    })
+}
+
+/// Reads and parses a post. Unchanged posts are taken from the cache.
+fn load_post(path: &Path, cache: &mut BuildCache) -> Result<Post, Vec<Diagnostic>> {
+    let source = read_source(path).map_err(|err| vec![err])?;
+    cache.post(path, &source, || parse_post(path, &source))
+}
+
+fn parse_post(path: &Path, source: &str) -> Result<Post, Vec<Diagnostic>> {
+    let MdFile {
+        front_matter,
+        body,
+        offset,
+    } = split_file(path, source).map_err(|err| vec![err])?;
+    let mut errors = Vec::new();
+
+    let front_matter = match toml::from_str::<FrontMatter>(&front_matter) {
+        Ok(front_matter) => Some(front_matter),
+        Err(err) => {
+            errors.push(Diagnostic::from_toml(path, source, &front_matter, 2, &err));
+            None
+        }
+    };
+    let (title, date, updated, description, tags, mut aliases) = match front_matter {
+        Some(FrontMatter {
+            title,
+            date,
+            updated,
+            description,
+            taxonomies,
+            aliases,
+        }) => {
+            let missing = [
+                ("title", title.is_none()),
+                ("date", date.is_none()),
+                ("description", description.is_none()),
+            ];
+            for (field, _) in missing.into_iter().filter(|(_, missing)| *missing) {
+                errors.push(
+                    Diagnostic::new(path, format!("the front matter is missing `{field}`"))
+                        .at_line(source, 1)
+                        .in_field(field),
+                );
+            }
+            let aliases = aliases
+                .iter()
+                .filter_map(|alias| match alias_name(alias) {
+                    // These would lead out of `/tui/blog` rather than to the post
+                    "" | "." | ".." => {
+                        errors.push(
+                            Diagnostic::new(path, format!("the alias `{alias}` has no name"))
+                                .in_field("aliases"),
+                        );
+                        None
+                    }
+                    name if post_slug(name).is_empty() => {
+                        errors.push(
+                            Diagnostic::new(path, format!("the alias `{alias}` has no name"))
+                                .in_field("aliases"),
+                        );
+                        None
+                    }
+                    name => Some(name.to_owned()),
+                })
+                .collect();
+            (title, date, updated, description, taxonomies.tags, aliases)
+        }
+        None => (None, None, None, None, Vec::new(), Vec::new()),
+    };
+    let summary = description.map(|description| {
+        description.parse::<Markdown>().map_err(|err| {
+            Diagnostic::new(path, format!("invalid description: {}", err.message))
+                .in_field("description")
+        })
+    });
+    let body = parse_body(path, source, &body, offset);
+
+    match (title, date, summary, body) {
+        (Some(title), Some(date), Some(Ok(summary)), Ok(body)) if errors.is_empty() => {
+            let stem = path.file_stem().unwrap().to_string_lossy();
+            let real_name = post_slug(&stem);
+            // The post used to be found by its file name as it was written, so that still leads to
+            // it
+            if stem != real_name && !aliases.iter().any(|alias| *alias == stem) {
+                aliases.push(stem.into_owned());
+            }
+            let summary = PostSummary {
+                title,
+                real_name,
+                summary,
+                create_on: date.to_string(),
+                last_edit: updated.and_then(|updated| {
+                    DateTime::parse_from_rfc3339(&timestamp(&updated.to_string()))
+                        .ok()
+                        .map(|updated| updated.with_timezone(&Utc))
+                }),
+                tags,
+                aliases,
+            };
+            Ok(Post { summary, body })
+        }
+        (_, _, summary, body) => {
+            errors.extend(summary.and_then(Result::err));
+            errors.extend(body.err().into_iter().flatten());
+            Err(errors)
+        }
+    }
+}
+
+/// Records the names that a post can be found by (its slug and the slugs of its aliases),
+/// reporting any that already lead to another post.
+fn claim_names(names: &mut HashMap<String, PathBuf>, path: &Path, post: &Post) -> Vec<Diagnostic> {
+    let own = post_slug(&post.summary.real_name);
+    let aliases = post
+        .summary
+        .aliases
+        .iter()
+        .map(|alias| (post_slug(alias), Some("aliases")))
+        .filter(|(alias, _)| *alias != own);
+    std::iter::once((own.clone(), None))
+        .chain(aliases)
+        .filter_map(|(name, field)| match names.get(&name) {
+            Some(other) if other != path => {
+                let err = Diagnostic::new(
+                    path,
+                    format!(
+                        "`{name}` already leads to `{}`",
+                        other.file_name().unwrap_or_default().to_string_lossy()
+                    ),
+                );
+                Some(match field {
+                    Some(field) => err.in_field(field),
+                    None => err,
+                })
+            }
+            Some(_) => None,
+            None => {
+                names.insert(name, path.to_owned());
+                None
+            }
+        })
+        .collect()
+}
+
+/// The name of the post at an alias, which is the last part of its path (e.g. `old-name` for
+/// `/blog/old-name/` or `old-name.html`).
+fn alias_name(alias: &str) -> &str {
+    let path = alias.trim_matches('/');
+    let path = path
+        .strip_suffix("/index.html")
+        .or_else(|| path.strip_suffix(".html"))
+        .unwrap_or(path);
+    path.rsplit('/').next().unwrap_or_default()
+}
+
+/// Parses the markdown of a page or post, which starts `offset` lines into the file, expanding
+/// its shortcodes.
+fn parse_body(
+    path: &Path,
+    source: &str,
+    body: &str,
+    offset: usize,
+) -> Result<Markdown, Vec<Diagnostic>> {
+    Shortcodes::default().parse(body).map_err(|err| match err {
+        ShortcodeParseError::Shortcodes(errors) => errors
+            .into_iter()
+            .map(|err| Diagnostic::new(path, err.message).at_line(source, err.line + offset))
+            .collect(),
+        ShortcodeParseError::Markdown(err) => vec![md_diagnostic(path, source, offset, err)],
+    })
+}
+
+/// Turns an error from parsing markdown into a diagnostic. The line of the error is relative to
+/// the markdown, which starts `offset` lines into the file.
+fn md_diagnostic(path: &Path, source: &str, offset: usize, err: MdParseError) -> Diagnostic {
+    let digest = Diagnostic::new(path, err.message);
+    match err.line {
+        Some(line) => digest.at_line(source, line + offset),
+        None => digest,
+    }
+}
+
+/// The paths of every post in the content directory, sorted by name.
+fn post_paths(content: &Path) -> Result<Vec<PathBuf>, Diagnostic> {
+    let files = std::fs::read_dir(content).map_err(|err| {
+        Diagnostic::new(
+            content,
+            format!("could not read the content directory: {err}"),
+        )
+    })?;
+    let mut posts: Vec<_> = files
+        .filter_map(|file| Some(file.ok()?.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "md")
+                && path.file_name().is_some_and(|name| name != "_index.md")
+        })
+        .collect();
+    posts.sort();
+    Ok(posts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{alias_name, Site, HOME_PAGE, PROJECTS_PAGE};
+    use crate::{
+        cache::BuildCache,
+        testing::{self, files, repo},
+    };
+
+    #[test]
+    fn all_errors_are_reported() {
+        let content = files([
+            (HOME_PAGE, "+++\n+++\nHello"),
+            (PROJECTS_PAGE, "+++\n+++\n\n| a |\n|---|\n| 1 |"),
+            (
+                "good.md",
+                "+++\ntitle = \"Good\"\ndate = 2024-01-01\ndescription = \"Hi\"\n+++\nHi",
+            ),
+            (
+                "bad-date.md",
+                "+++\ntitle = \"Bad\"\ndate = 2024-13-01\ndescription = \"Hi\"\n+++\nHi",
+            ),
+            (
+                "no-title.md",
+                "+++\ndate = 2024-01-01\ndescription = \"Hi\"\n+++\nHi",
+            ),
+        ]);
+
+        let config = repo().join("config.toml");
+        let errors = Site::load(content.path(), &config, &mut BuildCache::default()).unwrap_err();
+
+        assert_eq!(errors.len(), 3);
+        let table = errors
+            .iter()
+            .find(|e| e.file.ends_with(PROJECTS_PAGE))
+            .unwrap();
+        assert_eq!(table.line.as_ref().unwrap().0, 4);
+        let date = errors
+            .iter()
+            .find(|e| e.file.ends_with("bad-date.md"))
+            .unwrap();
+        assert_eq!(date.field.as_deref(), Some("date"));
+        assert_eq!(date.line.as_ref().unwrap().0, 3);
+        let title = errors
+            .iter()
+            .find(|e| e.file.ends_with("no-title.md"))
+            .unwrap();
+        assert_eq!(title.field.as_deref(), Some("title"));
+    }
+
+    #[test]
+    fn aliases_redirect_to_posts() {
+        let post = |aliases: &str| {
+            format!(
+                "+++\ntitle = \"A\"\ndate = 2024-01-01\ndescription = \"Hi\"\naliases = [{aliases}]\n+++\nHi"
+            )
+        };
+        let content = files([
+            (HOME_PAGE, "+++\n+++\nHello"),
+            (PROJECTS_PAGE, "+++\n+++\nHello"),
+            (
+                "New-Name.md",
+                &post(r#""/blog/old-name/", "Older Name", "new-name""#),
+            ),
+        ]);
+        let write =
+            |name: &str, data: &str| std::fs::write(content.path().join(name), data).unwrap();
+        let config = repo().join("config.toml");
+        let load = || Site::load(content.path(), &config, &mut BuildCache::default());
+
+        let site = load().unwrap();
+        assert_eq!(site.posts[0].summary.real_name, "new-name");
+        assert_eq!(
+            site.aliases().into_iter().collect::<Vec<_>>(),
+            [
+                ("New-Name".to_owned(), "new-name".to_owned()),
+                ("old-name".to_owned(), "new-name".to_owned()),
+                ("older-name".to_owned(), "new-name".to_owned()),
+            ]
+        );
+        let redirects = site.redirects();
+        let routes: Vec<_> = redirects.iter().map(|(route, _)| route.as_str()).collect();
+        assert_eq!(
+            routes,
+            [
+                "tui/blog/New-Name",
+                "tui/blog/Older Name",
+                "tui/blog/old-name",
+                "tui/blog/older-name"
+            ]
+        );
+        assert!(redirects
+            .iter()
+            .all(|(_, url)| url == "https://avid-rustacean.dev/tui/blog/new-name"));
+
+        // Aliases can't lead to more than one post
+        write("old-name.md", &post(""));
+        write("other.md", &post(r#""new-name""#));
+        write("nameless.md", &post(r#""/""#));
+        write("dots.md", &post(r#""/blog/../", ".", "%2E%2E""#));
+        let errors = load().unwrap_err();
+        let mut messages: Vec<_> = errors.iter().map(|err| err.message.as_str()).collect();
+        messages.sort();
+        assert_eq!(
+            messages,
+            [
+                "`new-name` already leads to `New-Name.md`",
+                "`old-name` already leads to `New-Name.md`",
+                "the alias `%2E%2E` has no name",
+                "the alias `.` has no name",
+                "the alias `/` has no name",
+                "the alias `/blog/../` has no name",
+            ]
+        );
+
+        assert_eq!(alias_name("/blog/old/"), "old");
+        assert_eq!(alias_name("old.html"), "old");
+        assert_eq!(alias_name("blog/old/index.html"), "old");
+    }
+
+    #[test]
+    fn renamed_files_still_lead_to_posts() {
+        let site = Site::load(
+            &repo().join("content"),
+            &repo().join("config.toml"),
+            &mut BuildCache::default(),
+        )
+        .unwrap();
+        let (old, new) = (
+            "RFFP-0-on-the-rust-learning-curve",
+            "rffp-0-on-the-rust-learning-curve",
+        );
+        assert!(site.posts.iter().any(|post| post.summary.real_name == new));
+        assert_eq!(site.aliases().get(old).map(String::as_str), Some(new));
+        assert!(site.redirects().contains(&(
+            format!("tui/blog/{old}"),
+            format!("https://avid-rustacean.dev/tui/blog/{new}")
+        )));
+    }
+
+    #[test]
+    fn posts_are_paginated() {
+        let mut site = testing::site();
+        site.posts = (1..=7)
+            .map(|i| testing::post(&format!("post-{i}"), "2024-01-01", &[]))
+            .collect();
+        site.paginate_by = Some(3);
+        let names = |pages: &[avid_rustacean_model::PostPage]| -> Vec<Vec<String>> {
+            pages
+                .iter()
+                .map(|page| page.posts.iter().map(|p| p.real_name.clone()).collect())
+                .collect()
+        };
+        let pages = site.post_pages();
+        assert_eq!(
+            names(&pages),
+            [
+                vec!["post-7", "post-6", "post-5"],
+                vec!["post-4", "post-3", "post-2"],
+                vec!["post-1"],
+            ]
+        );
+        assert!(pages
+            .iter()
+            .enumerate()
+            .all(|(i, page)| page.page == i + 1 && page.pages == 3));
+        let json: Vec<_> = site.json().into_iter().map(|(name, _)| name).collect();
+        assert!(json.contains(&"posts-3.json".to_owned()));
+        assert!(!json.contains(&"posts-4.json".to_owned()));
+
+        site.paginate_by = None;
+        assert_eq!(site.post_pages().len(), 1);
+        site.posts.clear();
+        let pages = site.post_pages();
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0].posts.is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{html::escape, site::Site};
+
+/// The name of the sitemap, which sits next to Zola's `sitemap.xml`.
+pub(crate) const SITEMAP: &str = "sitemap-tui.xml";
+
+/// The name of the robots.txt fragment. Zola's `robots.txt` template pulls this in.
+pub(crate) const ROBOTS: &str = "robots-tui.txt";
+
+/// Generates a sitemap of every route of the TUI. Each route is given the time that its content
+/// last changed, when that is known.
+pub(crate) fn sitemap(site: &Site) -> String {
+    let base_url = site.config.base_url.trim_end_matches('/');
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+"#,
+    );
+    for route in site.routes() {
+        xml.push_str("  <url>\n");
+        xml.push_str(&format!(
+            "    <loc>{}</loc>\n",
+            escape(&format!("{base_url}/{}/", route.path))
+        ));
+        if let Some(updated) = route.updated {
+            xml.push_str(&format!("    <lastmod>{}</lastmod>\n", escape(&updated)));
+        }
+        xml.push_str("  </url>\n");
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+/// Generates the lines to add to `robots.txt` so that crawlers can find the TUI's sitemap.
+pub(crate) fn robots(site: &Site) -> String {
+    format!(
+        "Sitemap: {}/{SITEMAP}\n",
+        site.config.base_url.trim_end_matches('/')
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime};
+    use roxmltree::{Document, Node};
+
+    use crate::{cache::BuildCache, site::Site, testing::repo};
+
+    use super::{robots, sitemap};
+
+    const NAMESPACE: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+    const XSD: &str = "http://www.w3.org/2001/XMLSchema";
+
+    /// The value of a facet of a restriction, e.g. `maxLength`.
+    fn facet<'a>(restriction: &Node<'a, '_>, name: &str) -> Option<&'a str> {
+        restriction
+            .children()
+            .find(|node| node.has_tag_name((XSD, name)))
+            .and_then(|node| node.attribute("value"))
+    }
+
+    /// Validates a sitemap against the sitemap schema (`schemas/sitemap.xsd`). This understands
+    /// just enough XML Schema to handle that one schema: the sequence of elements in each `url`,
+    /// and the facets of the simple types that their values must follow.
+    fn validate(sitemap: &str) -> Result<(), String> {
+        let schema_src = include_str!("../schemas/sitemap.xsd");
+        let schema = Document::parse(schema_src).unwrap();
+        let xsd = |node: &Node<'_, '_>, name: &str| node.has_tag_name((XSD, name));
+        let named = |kind: &str, name: &str| {
+            schema
+                .descendants()
+                .find(|node| xsd(node, kind) && node.attribute("name") == Some(name))
+                .unwrap()
+        };
+        // Everything that a `url` element can contain, in order, with whether it is required
+        let fields: Vec<_> = named("complexType", "tUrl")
+            .descendants()
+            .filter(|node| xsd(node, "element"))
+            .map(|node| {
+                (
+                    node.attribute("name").unwrap(),
+                    node.attribute("type").unwrap(),
+                    node.attribute("minOccurs") != Some("0"),
+                )
+            })
+            .collect();
+
+        let check_value = |ty: &str, value: &str| -> Result<(), String> {
+            let ty = named("simpleType", ty);
+            let bases: Vec<_> = ty
+                .descendants()
+                .filter(|node| xsd(node, "restriction"))
+                .collect();
+            // A union only needs one of its members to match
+            let mut errors = Vec::new();
+            for restriction in &bases {
+                let ok = match restriction.attribute("base").unwrap() {
+                    "xsd:anyURI" => {
+                        let len = value.chars().count();
+                        let min: usize = facet(restriction, "minLength").unwrap().parse().unwrap();
+                        let max: usize = facet(restriction, "maxLength").unwrap().parse().unwrap();
+                        (min..=max).contains(&len) && value.contains("://")
+                    }
+                    "xsd:date" => NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+                    "xsd:dateTime" => {
+                        DateTime::parse_from_rfc3339(value).is_ok()
+                            || NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").is_ok()
+                    }
+                    "xsd:string" => restriction
+                        .children()
+                        .filter(|node| xsd(node, "enumeration"))
+                        .any(|node| node.attribute("value") == Some(value)),
+                    "xsd:decimal" => value.parse::<f64>().is_ok_and(|value| {
+                        let min: f64 = facet(restriction, "minInclusive").unwrap().parse().unwrap();
+                        let max: f64 = facet(restriction, "maxInclusive").unwrap().parse().unwrap();
+                        (min..=max).contains(&value)
+                    }),
+                    base => panic!("unexpected base type {base}"),
+                };
+                if ok {
+                    return Ok(());
+                }
+                errors.push(restriction.attribute("base").unwrap());
+            }
+            Err(format!("`{value}` is not a valid {}", errors.join(" or ")))
+        };
+
+        let doc = Document::parse(sitemap).map_err(|err| err.to_string())?;
+        let root = doc.root_element();
+        if root.tag_name().namespace() != Some(NAMESPACE) || root.tag_name().name() != "urlset" {
+            return Err("the root element must be a `urlset`".to_owned());
+        }
+        let ours = |node: &Node<'_, '_>| {
+            node.is_element() && node.tag_name().namespace() == Some(NAMESPACE)
+        };
+        let urls: Vec<_> = root.children().filter(ours).collect();
+        if urls.is_empty() || urls.len() > 50_000 {
+            return Err(format!(
+                "a sitemap needs 1 to 50,000 urls, not {}",
+                urls.len()
+            ));
+        }
+        for url in urls {
+            if url.tag_name().name() != "url" {
+                return Err(format!("unexpected `{}` element", url.tag_name().name()));
+            }
+            let mut expected = fields.iter();
+            for child in url.children().filter(ours) {
+                let name = child.tag_name().name();
+                // Skip over the optional elements that were left out
+                let (_, ty, _) = loop {
+                    match expected.next() {
+                        Some(field) if field.0 == name => break field,
+                        Some((skipped, _, true)) => {
+                            return Err(format!("`{skipped}` is missing before `{name}`"))
+                        }
+                        Some(_) => {}
+                        None => return Err(format!("unexpected or out of order `{name}`")),
+                    }
+                };
+                check_value(ty, child.text().unwrap_or_default())?;
+            }
+            if let Some((missing, _, _)) = expected.find(|(_, _, required)| *required) {
+                return Err(format!("`{missing}` is missing"));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn sitemap_follows_the_schema() {
+        let site = Site::load(
+            &repo().join("content"),
+            &repo().join("config.toml"),
+            &mut BuildCache::default(),
+        )
+        .unwrap();
+        let sitemap = sitemap(&site);
+        validate(&sitemap).unwrap();
+        assert!(sitemap.contains("<loc>https://avid-rustacean.dev/tui/</loc>"));
+        assert!(sitemap.contains("<loc>https://avid-rustacean.dev/tui/projects/</loc>"));
+        assert!(sitemap.contains("<loc>https://avid-rustacean.dev/tui/archive/</loc>"));
+        for post in &site.posts {
+            let loc = format!(
+                "<loc>https://avid-rustacean.dev/tui/blog/{}/</loc>",
+                post.summary.real_name
+            );
+            assert!(sitemap.contains(&loc), "{loc} is missing");
+        }
+        assert!(sitemap.contains("<lastmod>2024-02-28T00:00:00+00:00</lastmod>"));
+        assert_eq!(
+            robots(&site),
+            "Sitemap: https://avid-rustacean.dev/sitemap-tui.xml\n"
+        );
+    }
+
+    #[test]
+    fn invalid_sitemaps_are_caught() {
+        let sitemap = |url: &str| {
+            format!(r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">{url}</urlset>"#)
+        };
+        validate(&sitemap("<url><loc>https://example.com/</loc></url>")).unwrap();
+        validate(&sitemap(
+            "<url><loc>https://example.com/</loc><lastmod>2024-02-28</lastmod>\
+             <changefreq>weekly</changefreq><priority>0.5</priority></url>",
+        ))
+        .unwrap();
+        for bad in [
+            "",
+            "<url><lastmod>2024-02-28</lastmod></url>",
+            "<url><loc>/tui/</loc></url>",
+            "<url><loc>https://example.com/</loc><lastmod>yesterday</lastmod></url>",
+            "<url><lastmod>2024-02-28</lastmod><loc>https://example.com/</loc></url>",
+            "<url><loc>https://example.com/</loc><changefreq>sometimes</changefreq></url>",
+            "<url><loc>https://example.com/</loc><priority>2</priority></url>",
+            "<url><loc>https://example.com/</loc><title>Hi</title></url>",
+        ] {
+            assert!(validate(&sitemap(bad)).is_err(), "{bad} should be invalid");
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use avid_rustacean_frontend::{
+    app::{PageData, TermApp},
+    palette::{indexed_color_str, set_code_theme},
+    site::set_site,
+};
+use avid_rustacean_model::{Archive, CodeTheme};
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Modifier},
+};
+
+use crate::{html::escape, site::Site};
+
+/// The number of columns that pages are drawn with.
+pub(crate) const WIDTH: u16 = 120;
+/// The number of lines in the first screen of a page.
+const SCREEN_HEIGHT: u16 = 40;
+
+/// The id of the element that holds the first screen of a page until the frontend replaces it.
+const SNAPSHOT_ID: &str = "tui-snapshot";
+
+/// The data of the page at each route (see [`Site::routes`]). Only the first page of the blog index
+/// has a route of its own.
+pub(crate) fn pages(site: &Site) -> Vec<(String, PageData)> {
+    let mut digest = vec![
+        ("tui".to_owned(), PageData::Home(site.home.clone())),
+        (
+            "tui/projects".to_owned(),
+            PageData::AllProjects(site.projects.clone()),
+        ),
+        (
+            "tui/blog".to_owned(),
+            PageData::Blog(site.post_pages().swap_remove(0)),
+        ),
+        (
+            "tui/archive".to_owned(),
+            PageData::Archive(Archive::new(&site.posts)),
+        ),
+    ];
+    digest.extend(site.posts.iter().map(|post| {
+        (
+            format!("tui/blog/{}", post.summary.real_name),
+            PageData::Post(post.clone()),
+        )
+    }));
+    digest
+}
+
+/// Draws every page with the frontend's own drawing code, returning the HTML to put in the body of
+/// each route's `index.html`. There are two copies of each page: the first screen, which is shown
+/// until the frontend takes over, and the whole page in a `<noscript>` for readers (and crawlers)
+/// without JavaScript.
+pub(crate) fn snapshots(site: &Site, theme: Option<CodeTheme>) -> Vec<(String, String)> {
+    set_site(site.config.site_info());
+    if let Some(theme) = theme {
+        set_code_theme(theme);
+    }
+    pages(site)
+        .into_iter()
+        .map(|(route, data)| {
+            let app = TermApp::with_data(data);
+            let screen = to_html(&app.snapshot(WIDTH, Some(SCREEN_HEIGHT)));
+            let page = to_html(&app.snapshot(WIDTH, None));
+            let html = format!(
+                "<pre id=\"{SNAPSHOT_ID}\" {PRE_STYLE}>{screen}</pre>\
+                 <noscript><style>#{SNAPSHOT_ID} {{ display: none; }}</style>\
+                 <pre {PRE_STYLE}>{page}</pre></noscript>"
+            );
+            (route, html)
+        })
+        .collect()
+}
+
+/// Centers the snapshot like the frontend's terminal.
+const PRE_STYLE: &str = r#"style="width: fit-content; margin: auto;""#;
+
+/// Turns a drawn buffer into HTML, with a line for each row and a styled `<span>` for each run of
+/// cells that look the same.
+pub(crate) fn to_html(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut digest = String::new();
+    for y in area.top()..area.bottom() {
+        let mut run = String::new();
+        let mut style = String::new();
+        for x in area.left()..area.right() {
+            let cell = buffer.get(x, y);
+            let cell_style = css(cell.fg, cell.bg, cell.modifier);
+            if cell_style != style {
+                push_span(&mut digest, &style, &run);
+                run.clear();
+                style = cell_style;
+            }
+            run.push_str(cell.symbol());
+        }
+        push_span(&mut digest, &style, &run);
+        digest.push('\n');
+    }
+    digest
+}
+
+fn push_span(html: &mut String, style: &str, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match style {
+        "" => html.push_str(&escape(text)),
+        style => html.push_str(&format!("<span style=\"{style}\">{}</span>", escape(text))),
+    }
+}
+
+/// The inline CSS for a cell. Modifiers without a CSS equivalent (like the one that marks spans
+/// for hydration) are left out.
+fn css(fg: Color, bg: Color, modifier: Modifier) -> String {
+    let mut digest = Vec::new();
+    if let Some(fg) = css_color(fg) {
+        digest.push(format!("color: {fg}"));
+    }
+    if let Some(bg) = css_color(bg) {
+        digest.push(format!("background-color: {bg}"));
+    }
+    if modifier.contains(Modifier::BOLD) {
+        digest.push("font-weight: bold".to_owned());
+    }
+    if modifier.contains(Modifier::ITALIC) {
+        digest.push("font-style: italic".to_owned());
+    }
+    if modifier.contains(Modifier::UNDERLINED) {
+        digest.push("text-decoration: underline".to_owned());
+    }
+    digest.join("; ")
+}
+
+fn css_color(color: Color) -> Option<String> {
+    match color {
+        Color::Indexed(i) if i < 16 => Some(indexed_color_str(i).to_owned()),
+        Color::Rgb(r, g, b) => Some(format!("#{r:02x}{g:02x}{b:02x}")),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{
+        buffer::Buffer,
+        layout::Rect,
+        style::{Color, Modifier, Style},
+    };
+
+    use super::{pages, snapshots, to_html};
+    use crate::{cache::BuildCache, site::Site, testing::repo};
+
+    #[test]
+    fn buffers_become_html() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 6, 2));
+        buffer.set_string(0, 0, "a<b", Style::new().fg(Color::Indexed(12)));
+        buffer.set_string(
+            3,
+            0,
+            "cd",
+            Style::new()
+                .bg(Color::Rgb(1, 2, 3))
+                .add_modifier(Modifier::BOLD),
+        );
+        buffer.set_string(0, 1, "e", Style::new());
+        assert_eq!(
+            to_html(&buffer),
+            "<span style=\"color: #b8bb26\">a&lt;b</span>\
+             <span style=\"background-color: #010203; font-weight: bold\">cd</span> \n\
+             e     \n"
+        );
+    }
+
+    #[test]
+    fn every_route_is_prerendered() {
+        let site = Site::load(
+            &repo().join("content"),
+            &repo().join("config.toml"),
+            &mut BuildCache::default(),
+        )
+        .unwrap();
+        let routes: Vec<_> = site.routes().into_iter().map(|route| route.path).collect();
+        let pages: Vec<_> = pages(&site).into_iter().map(|(route, _)| route).collect();
+        assert_eq!(pages, routes);
+
+        let snapshots = snapshots(&site, None);
+        let (_, post) = snapshots
+            .iter()
+            .find(|(route, _)| route.ends_with(&site.posts[0].summary.real_name))
+            .unwrap();
+        let (screen, page) = post.split_once("<noscript>").unwrap();
+        assert_eq!(screen.lines().count(), 41);
+        assert!(page.lines().count() > 40);
+        // The whole post is in the page, including its last line
+        let last = site.posts[0].body.0.last().unwrap();
+        let text = format!("{last:?}");
+        let word = text
+            .split(|c: char| !c.is_alphanumeric())
+            .rfind(|word| word.len() > 4)
+            .unwrap();
+        assert!(page.contains(word), "{word} is missing");
+        assert!(!screen.contains("<noscript>"));
+    }
+}
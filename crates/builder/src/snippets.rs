@@ -0,0 +1,275 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    path::Path,
+    process::Command,
+};
+
+use avid_rustacean_model::{Markdown, MdNode, ParsedCode, RUSTDOC_ATTRIBUTES};
+
+use crate::{diagnostics::Diagnostic, site::Site};
+
+/// A Rust code block from a post.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Snippet {
+    /// The name of the post's markdown file.
+    pub(crate) post: String,
+    /// The one-indexed number of the code block within the post, counting all code blocks.
+    pub(crate) block: usize,
+    pub(crate) code: ParsedCode,
+}
+
+impl Snippet {
+    /// The path to the item in the scratch crate whose doc comment holds this snippet, given the
+    /// names of the modules (see [`module_names`]).
+    fn item_path(&self, modules: &HashMap<&str, String>) -> String {
+        format!("{}::Block{}", modules[self.post.as_str()], self.block)
+    }
+}
+
+/// Extracts every Rust code block from every post, compiles them as doctests in a scratch crate,
+/// and reports each snippet that failed.
+pub(crate) fn test_snippets(site: &Site, scratch: &Path) -> Result<(), Vec<Diagnostic>> {
+    let mut snippets = Vec::new();
+    for post in &site.posts {
+        let path = site.page_path(&post.summary.real_name);
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        snippets.extend(extract_snippets(&name, post.body.clone()));
+    }
+    println!("Testing {} Rust snippets", snippets.len());
+
+    write_crate(scratch, &snippets).map_err(|err| {
+        vec![Diagnostic::new(
+            scratch,
+            format!("could not write the crate: {err}"),
+        )]
+    })?;
+    let manifest = scratch.join("Cargo.toml");
+    let output = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()))
+        .args(["test", "--doc", "--no-fail-fast", "--manifest-path"])
+        .arg(&manifest)
+        .output()
+        .map_err(|err| {
+            vec![Diagnostic::new(
+                &manifest,
+                format!("could not run `cargo test`: {err}"),
+            )]
+        })?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let modules = module_names(&snippets);
+    let failed: Vec<_> = snippets
+        .iter()
+        .filter(|snippet| {
+            let path = snippet.item_path(&modules);
+            stdout.lines().any(|line| {
+                line.contains(&format!(" - {path} ")) && line.trim_end().ends_with("FAILED")
+            })
+        })
+        .map(|snippet| {
+            Diagnostic::new(
+                &site.content.join(&snippet.post),
+                format!("code block {} failed as a doctest", snippet.block),
+            )
+        })
+        .collect();
+    if failed.is_empty() && output.status.success() {
+        println!("All snippets passed");
+        return Ok(());
+    }
+    println!("{stdout}");
+    eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+    match failed.is_empty() {
+        // Something went wrong other than a snippet failing, e.g. the crate didn't build
+        true => Err(vec![Diagnostic::new(
+            &manifest,
+            "the snippets could not be tested",
+        )]),
+        false => Err(failed),
+    }
+}
+
+/// Collects all of the Rust code blocks from a post.
+pub(crate) fn extract_snippets(post: &str, md: Markdown) -> Vec<Snippet> {
+    md.0.into_iter()
+        .filter_map(|node| match node {
+            MdNode::Code(code) => Some(code),
+            _ => None,
+        })
+        .enumerate()
+        .filter(|(_, code)| code.info.is_rust())
+        .map(|(i, code)| Snippet {
+            post: post.to_owned(),
+            block: i + 1,
+            code,
+        })
+        .collect()
+}
+
+/// Writes out the scratch crate. Each post becomes a module and each snippet becomes the doc
+/// comment of an item in that module. This lets rustdoc handle the fence attributes (`ignore`,
+/// `no_run`, etc.) exactly like it does for any other doctest.
+fn write_crate(scratch: &Path, snippets: &[Snippet]) -> std::io::Result<()> {
+    let src = scratch.join("src");
+    std::fs::create_dir_all(&src)?;
+    let manifest = r#"[package]
+name = "avid-rustacean-snippets"
+version = "0.0.0"
+edition = "2021"
+publish = false
+
+# Keep this crate out of any workspace that it happens to be placed in
+[workspace]
+"#;
+    std::fs::write(scratch.join("Cargo.toml"), manifest)?;
+    std::fs::write(src.join("lib.rs"), crate_source(snippets))
+}
+
+/// Generates the source of the scratch crate's `lib.rs`.
+pub(crate) fn crate_source(snippets: &[Snippet]) -> String {
+    let modules = module_names(snippets);
+    let mut digest = String::from("#![allow(rustdoc::all)]\n");
+    let mut current = None;
+    for snippet in snippets {
+        if current != Some(&snippet.post) {
+            if current.is_some() {
+                digest.push_str("}\n");
+            }
+            writeln!(
+                digest,
+                "\n/// {}\npub mod {} {{",
+                snippet.post,
+                modules[snippet.post.as_str()]
+            )
+            .unwrap();
+            current = Some(&snippet.post);
+        }
+        let attrs: Vec<_> = snippet
+            .code
+            .info
+            .attributes
+            .iter()
+            .filter(|attr| {
+                RUSTDOC_ATTRIBUTES.contains(&attr.as_str()) || attr.starts_with("edition")
+            })
+            .map(String::as_str)
+            .collect();
+        let fence = fence(&snippet.code.source);
+        writeln!(digest, "    /// {fence}{}", attrs.join(",")).unwrap();
+        for line in snippet.code.source.lines() {
+            if line.is_empty() {
+                digest.push_str("    ///\n");
+            } else {
+                writeln!(digest, "    /// {}", escape_line(line)).unwrap();
+            }
+        }
+        writeln!(
+            digest,
+            "    /// {fence}\n    pub struct Block{};",
+            snippet.block
+        )
+        .unwrap();
+    }
+    if current.is_some() {
+        digest.push_str("}\n");
+    }
+    digest
+}
+
+/// A fence for a code block that is longer than any run of backticks in the code, so that the code
+/// can't end the block early.
+fn fence(code: &str) -> String {
+    let longest = code
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    "`".repeat(longest.max(2) + 1)
+}
+
+/// The source of a snippet is the code that is compiled, so any line that rustdoc would take to
+/// be hidden (or escaped) has its leading `#` escaped with another one.
+fn escape_line(line: &str) -> String {
+    let code = line.trim_start();
+    let indent = &line[..line.len() - code.len()];
+    match code.starts_with('#') {
+        true => format!("{indent}#{code}"),
+        false => line.to_owned(),
+    }
+}
+
+/// Names the module of each post. Posts whose files would share a name (e.g. `a-b.md` and
+/// `a_b.md`) are told apart by a number.
+fn module_names(snippets: &[Snippet]) -> HashMap<&str, String> {
+    let mut names = HashMap::new();
+    let mut taken = HashSet::new();
+    for snippet in snippets {
+        if names.contains_key(snippet.post.as_str()) {
+            continue;
+        }
+        let base = module_name(&snippet.post);
+        let mut name = base.clone();
+        for i in 2.. {
+            if taken.insert(name.clone()) {
+                break;
+            }
+            name = format!("{base}_{i}");
+        }
+        names.insert(snippet.post.as_str(), name);
+    }
+    names
+}
+
+/// Turns the name of a post's file into a valid module name.
+fn module_name(post: &str) -> String {
+    let stem = post.strip_suffix(".md").unwrap_or(post);
+    let name: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("post_{name}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crate_source, extract_snippets};
+
+    #[test]
+    fn snippets_become_doctests() {
+        let md = "Some text\n\n```rust,no_run\n# fn main() {\nloop {}\n# }\n```\n\n```toml\na = 1\n```\n\n```\nlet x = 1;\n```";
+        let snippets = extract_snippets("My-Post.md", md.parse().unwrap());
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].block, 1);
+        // The TOML block is counted but not extracted
+        assert_eq!(snippets[1].block, 3);
+
+        let src = crate_source(&snippets);
+        assert!(src.contains("pub mod post_my_post {"));
+        assert!(
+            src.contains("    /// ```no_run\n    /// fn main() {\n    /// loop {}\n    /// }\n")
+        );
+        assert!(src.contains("pub struct Block3;"));
+    }
+
+    #[test]
+    fn snippets_compile_as_shown() {
+        // An escaped `#` is shown, so it is compiled too, and a fence in the code doesn't end the
+        // doctest
+        let md = "````rust\n## not hidden\nlet s = \"\n```\n\";\n````";
+        let mut snippets = extract_snippets("a-b.md", md.parse().unwrap());
+        snippets.extend(extract_snippets("a_b.md", md.parse().unwrap()));
+        let src = crate_source(&snippets);
+        assert!(src.contains(
+            "    /// ````\n    /// ## not hidden\n    /// let s = \"\n    /// ```\n    /// \";\n    /// ````\n"
+        ));
+        // Files whose names make the same module are told apart
+        assert!(src.contains("pub mod post_a_b {"));
+        assert!(src.contains("pub mod post_a_b_2 {"));
+    }
+}
@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+use avid_rustacean_model::{HomePage, Markdown, Post, PostSummary};
+use tempfile::TempDir;
+
+use crate::{config::SiteConfig, site::Site};
+
+/// The root of the repo, whose content and config the tests build.
+pub(crate) fn repo() -> PathBuf {
+    let mut root: PathBuf = env!("CARGO_MANIFEST_DIR").parse().unwrap();
+    root.pop();
+    root.pop();
+    root
+}
+
+/// A temporary directory holding the given files (which can be in subdirectories). The directory
+/// is removed when it is dropped, even if the test fails.
+pub(crate) fn files<'a>(files: impl IntoIterator<Item = (&'a str, &'a str)>) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for (name, data) in files {
+        let path = dir.path().join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, data).unwrap();
+    }
+    dir
+}
+
+/// A post with nothing in it, titled after its name.
+pub(crate) fn post(name: &str, date: &str, tags: &[&str]) -> Post {
+    Post {
+        summary: PostSummary {
+            title: format!("The {name} post"),
+            real_name: name.to_owned(),
+            create_on: date.to_owned(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        },
+        body: Markdown::default(),
+    }
+}
+
+/// A small site at `https://example.com`, with an `old` post tagged `Rust` and a `new` post
+/// tagged `Rust` and `Web Dev`. Tests fill in whatever else they need.
+pub(crate) fn site() -> Site {
+    Site {
+        config: SiteConfig {
+            base_url: "https://example.com".to_owned(),
+            title: "Example".to_owned(),
+            author: None,
+            extra: Default::default(),
+        },
+        content: "content".into(),
+        home: HomePage::default(),
+        projects: Markdown::default(),
+        posts: vec![
+            post("old", "2023-12-17", &["Rust"]),
+            post("new", "2024-02-28", &["Rust", "Web Dev"]),
+        ],
+        paginate_by: None,
+    }
+}
@@ -0,0 +1,309 @@
+use avid_rustacean_frontend::{
+    palette::{indexed_color_rgb, set_code_theme},
+    utils::Markdown,
+};
+use avid_rustacean_model::{origin, CodeTheme};
+use ratatui::{
+    buffer::{Buffer, Cell},
+    style::{Color, Modifier},
+};
+use webatui::backend::HYDRATION;
+
+use crate::site::Site;
+
+/// The number of columns that posts are rendered with.
+const WIDTH: u16 = 80;
+
+/// Renders every post for reading in a terminal, with the same drawing code as the TUI. Each post
+/// gets two files: `<post>.ans`, with the TUI's colors and links as OSC 8 hyperlinks, and
+/// `<post>.txt`, which is plain text with its links listed at the end.
+pub(crate) fn renditions(site: &Site, theme: Option<CodeTheme>) -> Vec<(String, String)> {
+    if let Some(theme) = theme {
+        set_code_theme(theme);
+    }
+    site.posts
+        .iter()
+        .flat_map(|post| {
+            let md = Markdown::new(post.summary.title.clone(), post.body.clone());
+            let links: Vec<_> = md
+                .links()
+                .iter()
+                .map(|(text, url)| (text.as_str(), absolute(&site.config.base_url, url)))
+                .collect();
+            let buffer = md.render(WIDTH);
+            let name = &post.summary.real_name;
+            [
+                (format!("{name}.ans"), to_ansi(&buffer, &links)),
+                (format!("{name}.txt"), to_plain(&buffer, &links)),
+            ]
+        })
+        .collect()
+}
+
+/// Links within the site are relative to its root, but the text is read outside of it.
+fn absolute(base_url: &str, url: &str) -> String {
+    match url.starts_with('/') {
+        true => format!("{}{url}", origin(base_url)),
+        false => url.to_owned(),
+    }
+}
+
+/// Turns a drawn buffer into text with ANSI escape codes, with truecolor styles for each run of
+/// cells that look the same. The links are the text and URL of every link, in the order that they
+/// are in the document (see [`Linker`]).
+pub(crate) fn to_ansi(buffer: &Buffer, links: &[(&str, String)]) -> String {
+    let mut digest = String::new();
+    let mut linker = Linker::new(links);
+    for row in rows(buffer) {
+        for (cell, text) in runs(&row) {
+            digest.push_str(&sgr(cell));
+            match linker.link(cell, &text) {
+                Some(url) => digest.push_str(&format!("\x1b]8;;{url}\x1b\\{text}\x1b]8;;\x1b\\")),
+                None => digest.push_str(&text),
+            }
+        }
+        digest.push_str("\x1b[0m\n");
+    }
+    digest
+}
+
+/// Turns a drawn buffer into plain text, followed by a list of the links in the order that they
+/// first appear.
+pub(crate) fn to_plain(buffer: &Buffer, links: &[(&str, String)]) -> String {
+    let mut digest = String::new();
+    let mut found: Vec<(String, &str)> = Vec::new();
+    let mut linker = Linker::new(links);
+    for row in rows(buffer) {
+        let mut line = String::new();
+        for (cell, text) in runs(&row) {
+            if let Some(url) = linker.link(cell, &text) {
+                if found.iter().all(|(_, found)| *found != url) {
+                    found.push((text.trim().to_owned(), url));
+                }
+            }
+            line.push_str(&text);
+        }
+        digest.push_str(line.trim_end());
+        digest.push('\n');
+    }
+    if !found.is_empty() {
+        digest.push_str("\nLinks:\n");
+        for (text, url) in found {
+            digest.push_str(&format!("  {text}: {url}\n"));
+        }
+    }
+    digest
+}
+
+/// The cells of each row, without the blank cells at the end of it.
+fn rows(buffer: &Buffer) -> Vec<Vec<&Cell>> {
+    let area = buffer.area;
+    (area.top()..area.bottom())
+        .map(|y| {
+            let mut row: Vec<_> = (area.left()..area.right())
+                .map(|x| buffer.get(x, y))
+                .collect();
+            while row.last().is_some_and(|cell| *cell == &Cell::default()) {
+                row.pop();
+            }
+            row
+        })
+        .collect()
+}
+
+/// Splits a row into runs of cells that look the same, each with its first cell and its text.
+fn runs<'a>(row: &[&'a Cell]) -> Vec<(&'a Cell, String)> {
+    let mut digest: Vec<(&Cell, String)> = Vec::new();
+    for cell in row {
+        match digest.last_mut() {
+            Some((first, text)) if same_style(first, cell) => text.push_str(cell.symbol()),
+            _ => digest.push((cell, cell.symbol().to_owned())),
+        }
+    }
+    digest
+}
+
+fn same_style(a: &Cell, b: &Cell) -> bool {
+    a.fg == b.fg && a.bg == b.bg && a.modifier == b.modifier
+}
+
+/// Finds the links in a drawn buffer. Links are marked by the modifier that the TUI uses for
+/// hydration, and are drawn in the same order as they are in the document. So each run of linked
+/// cells is either more of the link before it (when a link is wrapped) or the first of the links
+/// after it that has the run's text, which keeps links with the same words apart.
+struct Linker<'a> {
+    links: &'a [(&'a str, String)],
+    /// The first link that hasn't been drawn yet.
+    next: usize,
+    /// The link that is being drawn and the part of its text that hasn't been drawn yet.
+    current: Option<(&'a str, &'a str)>,
+}
+
+impl<'a> Linker<'a> {
+    fn new(links: &'a [(&'a str, String)]) -> Self {
+        Self {
+            links,
+            next: 0,
+            current: None,
+        }
+    }
+
+    /// The URL of the link that a run of cells is part of, if it is part of one.
+    fn link(&mut self, cell: &Cell, text: &str) -> Option<&'a str> {
+        if !cell.modifier.contains(HYDRATION) {
+            return None;
+        }
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        if let Some((url, rest)) = self.current {
+            if let Some(rest) = rest.strip_prefix(text) {
+                self.current = Some((url, rest.trim_start()));
+                return Some(url);
+            }
+        }
+        let found = self.links[self.next..]
+            .iter()
+            .enumerate()
+            .find_map(|(i, (link, url))| Some((i, link.find(text)?, link, url)));
+        match found {
+            Some((i, start, link, url)) => {
+                self.next += i + 1;
+                self.current = Some((url, link[start + text.len()..].trim_start()));
+                Some(url)
+            }
+            // The run doesn't fit where it should, so settle for the first link that has its text
+            None => self
+                .links
+                .iter()
+                .find(|(link, _)| link.contains(text))
+                .map(|(_, url)| url.as_str()),
+        }
+    }
+}
+
+/// The escape code that resets the style and then sets the style of a cell.
+fn sgr(cell: &Cell) -> String {
+    let mut digest = String::from("\x1b[0");
+    if cell.modifier.contains(Modifier::BOLD) {
+        digest.push_str(";1");
+    }
+    if cell.modifier.contains(Modifier::ITALIC) {
+        digest.push_str(";3");
+    }
+    if cell.modifier.contains(Modifier::UNDERLINED) {
+        digest.push_str(";4");
+    }
+    if let Some((r, g, b)) = rgb(cell.fg) {
+        digest.push_str(&format!(";38;2;{r};{g};{b}"));
+    }
+    if let Some((r, g, b)) = rgb(cell.bg) {
+        digest.push_str(&format!(";48;2;{r};{g};{b}"));
+    }
+    digest.push('m');
+    digest
+}
+
+fn rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Indexed(i) if i < 16 => rgb(indexed_color_rgb(i)),
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{
+        buffer::Buffer,
+        layout::Rect,
+        style::{Color, Modifier, Style},
+    };
+    use webatui::backend::HYDRATION;
+
+    use super::{renditions, to_ansi, to_plain};
+    use crate::{cache::BuildCache, site::Site, testing::repo};
+
+    fn buffer() -> Buffer {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 12, 2));
+        buffer.set_string(0, 0, "See ", Style::new().add_modifier(Modifier::BOLD));
+        buffer.set_string(
+            4,
+            0,
+            "the docs",
+            Style::new().fg(Color::Indexed(12)).add_modifier(HYDRATION),
+        );
+        buffer.set_string(0, 1, "x", Style::new().bg(Color::Rgb(1, 2, 3)));
+        buffer
+    }
+
+    #[test]
+    fn buffers_become_ansi() {
+        let links = [("the docs", "https://docs.rs/".to_owned())];
+        assert_eq!(
+            to_ansi(&buffer(), &links),
+            "\x1b[0;1mSee \x1b[0;38;2;184;187;38m\x1b]8;;https://docs.rs/\x1b\\the docs\x1b]8;;\x1b\\\x1b[0m\n\
+             \x1b[0;48;2;1;2;3mx\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn buffers_become_plain_text() {
+        // Part of a link that was wrapped onto another line is still found
+        let links = [("read the docs", "https://docs.rs/".to_owned())];
+        assert_eq!(
+            to_plain(&buffer(), &links),
+            "See the docs\nx\n\nLinks:\n  the docs: https://docs.rs/\n"
+        );
+    }
+
+    #[test]
+    fn links_are_found_in_order() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 10, 3));
+        let link = Style::new().add_modifier(HYDRATION);
+        buffer.set_string(0, 0, "Rust", link);
+        buffer.set_string(0, 1, "Learn", link);
+        buffer.set_string(0, 2, "Rust", link);
+        let links = [
+            ("Rust", "https://rust-lang.org/".to_owned()),
+            ("Learn Rust", "https://doc.rust-lang.org/book/".to_owned()),
+        ];
+        assert_eq!(
+            to_plain(&buffer, &links),
+            "Rust\nLearn\nRust\n\nLinks:\n  Rust: https://rust-lang.org/\n  \
+             Learn: https://doc.rust-lang.org/book/\n"
+        );
+        // The second "Rust" is the end of the second link, not the first link again
+        assert!(to_ansi(&buffer, &links)
+            .contains("\x1b]8;;https://doc.rust-lang.org/book/\x1b\\Rust\x1b]8;;"));
+    }
+
+    #[test]
+    fn every_post_is_rendered() {
+        let site = Site::load(
+            &repo().join("content"),
+            &repo().join("config.toml"),
+            &mut BuildCache::default(),
+        )
+        .unwrap();
+        let texts = renditions(&site, None);
+        assert_eq!(texts.len(), 2 * site.posts.len());
+        let name = &site.posts[0].summary.real_name;
+        let (_, ans) = texts
+            .iter()
+            .find(|(n, _)| *n == format!("{name}.ans"))
+            .unwrap();
+        let (_, txt) = texts
+            .iter()
+            .find(|(n, _)| *n == format!("{name}.txt"))
+            .unwrap();
+        assert!(ans.contains(&site.posts[0].summary.title));
+        assert!(ans.contains("\x1b[0;1;38;2;"));
+        assert!(!txt.contains('\x1b'));
+        // Only the list of links is allowed to go past the edge
+        let (body, _) = txt.split_once("\nLinks:\n").unwrap();
+        assert!(body.lines().all(|line| line.chars().count() <= 80));
+        assert!(body.trim_start().starts_with(&site.posts[0].summary.title));
+    }
+}
@@ -1,18 +1,27 @@
 use webatui::{prelude::*, ScrollMotion};
 use yew::{Context, Properties};
-use yew_router::scope_ext::RouterScopeExt;
+use yew_router::{
+    history::{BrowserHistory, History},
+    scope_ext::RouterScopeExt,
+    Routable,
+};
 
 use crate::{
+    archive::{ArchiveMessage, ArchivePage},
     blog::{Blog, BlogMessage},
     home::{Home, HomeMessage},
-    palette::{GruvboxColor, GruvboxExt},
+    keyboard, live_reload,
+    palette::{load_code_theme, set_code_theme, CodeTheme, GruvboxColor, GruvboxExt},
     posts::{Post, PostMessage},
     project::{AllProjects, AllProjectsMessage},
+    search::{Search, SearchAction, SearchMessage},
+    site,
     utils::{padded_title, ScrollRef},
     Route,
 };
+use avid_rustacean_model::{Archive, HomePage, Markdown, PostPage, SiteInfo};
 use derive_more::From;
-use ratatui::{prelude::*, widgets::*};
+use ratatui::{backend::TestBackend, buffer::Buffer, prelude::*, widgets::*};
 
 /// This module contains all of the machinery to run the UI app. The UI app is a single page
 /// application consisting of the header, body, and footer. The body is changed when switching
@@ -23,12 +32,24 @@ use ratatui::{prelude::*, widgets::*};
 pub struct TermApp {
     /// The body of the UI
     body: AppBody,
+    /// The search prompt, while it is open.
+    search: Option<Search>,
 }
 
 impl TerminalApp for TermApp {
     type Message = TermAppMsg;
 
     fn setup(&mut self, ctx: &Context<WebTerminal<Self>>) {
+        live_reload::connect(
+            ctx.link()
+                .callback(|_| WebTermMessage::new(TermAppMsg::Reload)),
+        );
+        site::load(ctx);
+        load_code_theme(ctx);
+        keyboard::listen(
+            ctx.link()
+                .callback(|key| WebTermMessage::new(TermAppMsg::Key(key))),
+        );
         self.body.setup(ctx);
     }
 
@@ -39,26 +60,36 @@ impl TerminalApp for TermApp {
 
     fn update(&mut self, ctx: TermContext<'_, Self>, msg: Self::Message) -> bool {
         match msg {
-            TermAppMsg::ComponentMsg(msg) => self.body.update(ctx, msg),
-            TermAppMsg::Clicked(page) => {
-                match &page {
-                    AppBodyProps::Home => ctx.ctx().link().navigator().unwrap().push(&Route::Home),
-                    AppBodyProps::AllProjects => ctx
-                        .ctx()
-                        .link()
-                        .navigator()
-                        .unwrap()
-                        .push(&Route::AllProjects),
-                    AppBodyProps::Blog => ctx.ctx().link().navigator().unwrap().push(&Route::Blog),
-                    AppBodyProps::Post(name) => {
-                        ctx.ctx().link().navigator().unwrap().push(&Route::Post {
-                            name: name.to_owned(),
-                        });
-                    }
-                }
-                self.body = page.create_body();
+            TermAppMsg::ComponentMsg(msg) => self.body.update(msg),
+            // The content was rebuilt by `builder serve`, so the data for the current page is
+            // fetched again, along with the code theme
+            TermAppMsg::Reload => {
+                load_code_theme(ctx.ctx());
                 self.body.setup(ctx.ctx())
             }
+            TermAppMsg::Clicked(page) => self.open(ctx, page, false),
+            TermAppMsg::Moved(page) => self.open(ctx, page, true),
+            TermAppMsg::Key(key) => match &mut self.search {
+                Some(search) => match search.handle_key(&key) {
+                    SearchAction::Redraw => {}
+                    SearchAction::Close => self.close_search(),
+                    SearchAction::Open(page) => self.open(ctx, page, false),
+                },
+                None if key == "/" => self.search = Some(Search::open(ctx.ctx())),
+                None => match self.body.handle_key(&key) {
+                    Some(page) => self.open(ctx, page, false),
+                    None => return false,
+                },
+            },
+            TermAppMsg::OpenSearch => {
+                if self.search.is_none() {
+                    self.search = Some(Search::open(ctx.ctx()));
+                }
+            }
+            TermAppMsg::Site(info) => site::set_site(info),
+            TermAppMsg::CodeTheme(theme) => set_code_theme(theme),
+            // The index is kept even if the prompt was closed before it arrived
+            TermAppMsg::Search(msg) => Search::update(self.search.as_mut(), msg),
         }
         true
     }
@@ -68,27 +99,17 @@ impl TerminalApp for TermApp {
     }
 
     fn hydrate(&self, ctx: &Context<WebTerminal<Self>>, span: &mut DehydratedSpan) {
-        match span.text().trim() {
-            "Home" => span.on_click(
-                ctx.link()
-                    .callback(|_| WebTermMessage::new(AppBodyProps::Home)),
-            ),
-            "Projects" => span.on_click(
+        match self.link(span.text()) {
+            Some(Link::Page(page)) => span.on_click(
                 ctx.link()
-                    .callback(|_| WebTermMessage::new(AppBodyProps::AllProjects)),
+                    .callback(move |_| WebTermMessage::new(page.clone())),
             ),
-            "Blog" => span.on_click(
+            Some(Link::Url(url)) => span.hyperlink(url),
+            Some(Link::Search) => span.on_click(
                 ctx.link()
-                    .callback(|_| WebTermMessage::new(AppBodyProps::Blog)),
+                    .callback(|_| WebTermMessage::new(TermAppMsg::OpenSearch)),
             ),
-            "GUI" => span.hyperlink("https://avid-rustacean.dev".to_owned()),
-            "Repo" => span.hyperlink("https://github.com/TylerBloom/avid-rustacean".to_owned()),
-            "Email" => span.hyperlink("mailto:tylerbloom2222@gmail.com".to_owned()),
-            "RSS" => span.hyperlink("rss.xml".to_owned()),
-            "LinkedIn" => {
-                span.hyperlink("https://www.linkedin.com/in/tyler-bloom-aba0a4156/".to_owned())
-            }
-            _ => self.body.hydrate(ctx, span),
+            None => {}
         }
     }
 }
@@ -106,12 +127,38 @@ pub struct AppBody {
     scroll: ScrollRef,
 }
 
+/// The data of a page that has already been loaded. In the browser, each page fetches its own
+/// data, but elsewhere (e.g. when the builder prerenders pages) the data is handed to the page
+/// with [`TermApp::with_data`].
+#[derive(Debug, Clone)]
+pub enum PageData {
+    Home(HomePage),
+    AllProjects(Markdown),
+    Blog(PostPage),
+    Archive(Archive),
+    Post(avid_rustacean_model::Post),
+}
+
+impl PageData {
+    /// The page that shows this data.
+    pub fn props(&self) -> AppBodyProps {
+        match self {
+            Self::Home(_) => AppBodyProps::Home,
+            Self::AllProjects(_) => AppBodyProps::AllProjects,
+            Self::Blog(page) => AppBodyProps::Blog(page.page),
+            Self::Archive(_) => AppBodyProps::Archive,
+            Self::Post(post) => AppBodyProps::Post(post.summary.real_name.clone(), None),
+        }
+    }
+}
+
 /// The different main sections the user might find themselves in.
 #[derive(Debug, PartialEq, From, Clone)]
 enum AppBodyInner {
     Home(Home),
     AllProjects(AllProjects),
     Blog(Blog),
+    Archive(ArchivePage),
     Post(Post),
 }
 
@@ -139,12 +186,20 @@ impl AppBody {
         );
     }
 
-    fn hydrate(&self, ctx: &Context<WebTerminal<TermApp>>, span: &mut DehydratedSpan) {
-        self.inner.hydrate(ctx, span)
+    fn link(&self, text: &str) -> Option<Link> {
+        self.inner.link(text)
     }
 
-    fn update(&mut self, ctx: TermContext<'_, TermApp>, msg: ComponentMsg) {
-        self.inner.update(ctx, msg)
+    fn update(&mut self, msg: ComponentMsg) {
+        self.inner.update(msg)
+    }
+
+    /// Handles a key press that wasn't meant for the app, returning the page to switch to, if any.
+    fn handle_key(&self, key: &str) -> Option<AppBodyProps> {
+        match &self.inner {
+            AppBodyInner::Blog(blog) => blog.handle_key(key),
+            _ => None,
+        }
     }
 
     fn handle_scroll(&mut self, dir: ScrollMotion) {
@@ -157,11 +212,28 @@ impl AppBody {
 }
 
 impl AppBodyInner {
+    fn load(&mut self, data: PageData) {
+        match (self, data) {
+            (Self::Home(body), PageData::Home(home)) => body.update(HomeMessage::Data(home)),
+            (Self::AllProjects(body), PageData::AllProjects(projects)) => {
+                body.update(AllProjectsMessage::ProjectSummaries(projects))
+            }
+            (Self::Blog(body), PageData::Blog(page)) => body.load(page),
+            (Self::Archive(body), PageData::Archive(archive)) => {
+                body.update(ArchiveMessage::Archive(archive))
+            }
+            (Self::Post(body), PageData::Post(post)) => body.update(PostMessage::Post(post)),
+            // The app only loads data into the page that was made from it
+            (_, data) => unreachable!("{data:?} is not the data for this page"),
+        }
+    }
+
     fn draw(&self, scroll: &ScrollRef, chunk: Rect, frame: &mut Frame<'_>) {
         match self {
             Self::Home(home) => home.draw(scroll, chunk, frame),
             Self::AllProjects(projects) => projects.draw(scroll, chunk, frame),
             Self::Blog(blog) => blog.draw(scroll, chunk, frame),
+            Self::Archive(archive) => archive.draw(scroll, chunk, frame),
             Self::Post(post) => post.draw(scroll, chunk, frame),
         }
     }
@@ -171,24 +243,27 @@ impl AppBodyInner {
             AppBodyInner::Home(inner) => inner.setup(ctx),
             AppBodyInner::AllProjects(inner) => inner.setup(ctx),
             AppBodyInner::Blog(inner) => inner.setup(ctx),
+            AppBodyInner::Archive(inner) => inner.setup(ctx),
             AppBodyInner::Post(inner) => inner.setup(ctx),
         }
     }
 
-    fn hydrate(&self, ctx: &Context<WebTerminal<TermApp>>, span: &mut DehydratedSpan) {
+    fn link(&self, text: &str) -> Option<Link> {
         match self {
-            Self::Home(home) => home.hydrate(ctx, span),
-            Self::AllProjects(projects) => projects.hydrate(ctx, span),
-            Self::Blog(blog) => blog.hydrate(ctx, span),
-            Self::Post(post) => post.hydrate(ctx, span),
+            Self::Home(home) => home.link(text),
+            Self::AllProjects(projects) => projects.link(text),
+            Self::Blog(blog) => blog.link(text),
+            Self::Archive(archive) => archive.link(text),
+            Self::Post(post) => post.link(text),
         }
     }
 
-    fn update(&mut self, ctx: TermContext<'_, TermApp>, msg: ComponentMsg) {
+    fn update(&mut self, msg: ComponentMsg) {
         match (self, msg) {
             (Self::Home(body), ComponentMsg::Home(msg)) => body.update(msg),
-            (Self::AllProjects(body), ComponentMsg::AllProjects(msg)) => body.update(ctx, msg),
-            (Self::Blog(body), ComponentMsg::Blog(msg)) => body.update(ctx, msg),
+            (Self::AllProjects(body), ComponentMsg::AllProjects(msg)) => body.update(msg),
+            (Self::Blog(body), ComponentMsg::Blog(msg)) => body.update(msg),
+            (Self::Archive(body), ComponentMsg::Archive(msg)) => body.update(msg),
             (Self::Post(body), ComponentMsg::Post(msg)) => body.update(msg),
             _ => unreachable!("How did you get here? Open a PR, please"),
         }
@@ -199,6 +274,7 @@ impl AppBodyInner {
             Self::Home(home) => home.handle_scroll(dir),
             Self::AllProjects(projects) => projects.handle_scroll(dir),
             Self::Blog(blog) => blog.handle_scroll(dir),
+            Self::Archive(archive) => archive.handle_scroll(dir),
             Self::Post(post) => post.handle_scroll(dir),
         }
     }
@@ -209,8 +285,11 @@ impl AppBodyInner {
 pub enum AppBodyProps {
     Home,
     AllProjects,
-    Blog,
-    Post(String),
+    /// A page of the blog index, numbered from 1.
+    Blog(usize),
+    Archive,
+    /// A post, by its name, and the anchor of the heading to jump to, if any.
+    Post(String, Option<String>),
 }
 
 impl AppBodyProps {
@@ -218,17 +297,46 @@ impl AppBodyProps {
         let inner = match self {
             AppBodyProps::Home => AppBodyInner::Home(Home::create()),
             AppBodyProps::AllProjects => AppBodyInner::AllProjects(AllProjects::create()),
-            AppBodyProps::Blog => AppBodyInner::Blog(Blog::create()),
-            AppBodyProps::Post(name) => AppBodyInner::Post(Post::create(name)),
+            AppBodyProps::Blog(page) => AppBodyInner::Blog(Blog::create(page)),
+            AppBodyProps::Archive => AppBodyInner::Archive(ArchivePage::create()),
+            AppBodyProps::Post(name, anchor) => AppBodyInner::Post(Post::create(name, anchor)),
         };
         AppBody::new(inner)
     }
 }
 
+/// Where a link in the TUI leads. See [`TermApp::link`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Link {
+    /// Another page of the TUI.
+    Page(AppBodyProps),
+    /// A page outside of the TUI.
+    Url(String),
+    /// The search prompt.
+    Search,
+}
+
 #[derive(Debug, From)]
 pub enum TermAppMsg {
     Clicked(AppBodyProps),
     ComponentMsg(ComponentMsg),
+    /// Sent when the page should refetch its data. See [`live_reload`].
+    #[from(skip)]
+    Reload,
+    /// The name of a key that was pressed. See [`keyboard`].
+    #[from(skip)]
+    Key(String),
+    #[from(skip)]
+    OpenSearch,
+    /// The page has moved, e.g. a post that was renamed and is found by its old name. See
+    /// [`posts`](crate::posts).
+    #[from(skip)]
+    Moved(AppBodyProps),
+    Search(SearchMessage),
+    /// The site's config, which is fetched once. See [`site`].
+    Site(SiteInfo),
+    /// The colors of highlighted code. See [`load_code_theme`].
+    CodeTheme(CodeTheme),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -244,6 +352,7 @@ pub enum ComponentMsg {
     Home(HomeMessage),
     AllProjects(AllProjectsMessage),
     Blog(BlogMessage),
+    Archive(ArchiveMessage),
     Post(PostMessage),
 }
 
@@ -251,6 +360,144 @@ impl TermApp {
     pub fn new(props: AppBodyProps) -> Self {
         Self {
             body: props.create_body(),
+            search: None,
+        }
+    }
+
+    /// Creates the app with a page whose data is already loaded, without fetching anything.
+    pub fn with_data(data: PageData) -> Self {
+        Self::with_anchor(data, None)
+    }
+
+    /// Like [`TermApp::with_data`], but a post jumps to the heading with the given anchor. The
+    /// anchor is ignored for every other page.
+    pub fn with_anchor(data: PageData, anchor: Option<String>) -> Self {
+        let page = match data.props() {
+            AppBodyProps::Post(name, _) => AppBodyProps::Post(name, anchor),
+            page => page,
+        };
+        let mut app = Self::new(page);
+        app.body.inner.load(data);
+        app
+    }
+
+    /// Where the link with the given text leads, if there is one. Links are found by their text
+    /// (see [`DehydratedSpan`]), both when they are hydrated in the browser and when they are
+    /// followed elsewhere.
+    pub fn link(&self, text: &str) -> Option<Link> {
+        let page = |page| Some(Link::Page(page));
+        match text.trim() {
+            "Home" => page(AppBodyProps::Home),
+            "Projects" => page(AppBodyProps::AllProjects),
+            "Blog" => page(AppBodyProps::Blog(1)),
+            "Archive" => page(AppBodyProps::Archive),
+            "GUI" => Some(Link::Url(site::site().base_url)),
+            "Atom" => Some(Link::Url(site::url("atom.xml"))),
+            "JSON Feed" => Some(Link::Url(site::url("feed.json"))),
+            "Search (/)" => Some(Link::Search),
+            trimmed => site::site()
+                .links
+                .into_iter()
+                .find(|link| link.name == trimmed)
+                .map(|link| Link::Url(link.url))
+                .or_else(|| self.search.as_ref().and_then(|search| search.link(text)))
+                .or_else(|| self.body.link(text)),
+        }
+    }
+
+    /// Handles the key with the given name (e.g. `ArrowLeft`) outside of the browser, returning the
+    /// page to switch to, if any.
+    pub fn handle_key(&self, key: &str) -> Option<AppBodyProps> {
+        self.body.handle_key(key)
+    }
+
+    /// Scrolls the page by a number of lines (towards the bottom when positive) outside of the
+    /// browser. Scrolling past either end of the page is undone the next time that it is drawn.
+    pub fn scroll_by(&mut self, lines: isize) {
+        // The wheel moves the page the opposite way, e.g. scrolling up moves the page towards
+        // its bottom
+        let motion = match lines > 0 {
+            true => ScrollMotion::Up,
+            false => ScrollMotion::Down,
+        };
+        for _ in 0..lines.unsigned_abs() {
+            self.body.handle_scroll(motion);
+        }
+    }
+
+    /// The number of lines in the page, as of the last time that it was drawn.
+    pub fn content_length(&self) -> usize {
+        self.body.scroll.content_length()
+    }
+
+    /// Draws the app into a buffer outside of the browser. If there is no height, the buffer is
+    /// tall enough to fit all of the page's content without scrolling.
+    pub fn snapshot(&self, width: u16, height: Option<u16>) -> Buffer {
+        let draw = |height: u16| {
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            terminal
+                .draw(|frame| self.draw(frame.size(), frame))
+                .unwrap();
+            terminal.backend().buffer().clone()
+        };
+        match height {
+            Some(height) => draw(height),
+            None => {
+                // The length of the content is only known once it has been drawn
+                let buffer = draw(Self::CHROME_HEIGHT + 1);
+                let length = self.content_length() as u16;
+                match length {
+                    0 => buffer,
+                    length => draw(Self::CHROME_HEIGHT + length),
+                }
+            }
+        }
+    }
+
+    /// The number of lines that the header, footer, and the borders of the body take up.
+    pub const CHROME_HEIGHT: u16 = 3 + 3 + 2;
+
+    /// Switches to another page, closing the search prompt if it is open. If the page is a
+    /// redirect, it replaces the current page in the history so that going back skips it.
+    fn open(&mut self, ctx: TermContext<'_, Self>, page: AppBodyProps, redirect: bool) {
+        self.close_search();
+        let navigator = ctx.ctx().link().navigator().unwrap();
+        let (route, query, anchor) = match &page {
+            AppBodyProps::Home => (Route::Home, None, None),
+            AppBodyProps::AllProjects => (Route::AllProjects, None, None),
+            AppBodyProps::Blog(1) => (Route::Blog, None, None),
+            AppBodyProps::Blog(page) => (Route::Blog, Some([("page", *page)]), None),
+            AppBodyProps::Archive => (Route::Archive, None, None),
+            AppBodyProps::Post(name, anchor) => (
+                Route::Post {
+                    name: name.to_owned(),
+                },
+                None,
+                anchor.as_deref(),
+            ),
+        };
+        match (anchor, query, redirect) {
+            // Routes don't have anchors, so the URL is pushed along with its anchor directly
+            (Some(anchor), _, _) => {
+                let base = navigator.basename().unwrap_or_default();
+                let url = format!("{base}{}#{anchor}", route.to_path());
+                match redirect {
+                    true => BrowserHistory::new().replace(url),
+                    false => BrowserHistory::new().push(url),
+                }
+            }
+            (None, None, false) => navigator.push(&route),
+            (None, None, true) => navigator.replace(&route),
+            (None, Some(query), false) => navigator.push_with_query(&route, &query).unwrap(),
+            (None, Some(query), true) => navigator.replace_with_query(&route, &query).unwrap(),
+        }
+        self.body = page.create_body();
+        self.body.setup(ctx.ctx())
+    }
+
+    fn close_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.close();
         }
     }
 
@@ -266,6 +513,9 @@ impl TermApp {
         .split(area);
         self.draw_header(chunks[0], frame);
         self.body.draw(chunks[1], frame);
+        if let Some(search) = &self.search {
+            search.draw(chunks[1], frame);
+        }
         self.draw_footer(chunks[2], frame);
     }
 
@@ -275,13 +525,14 @@ impl TermApp {
             Line::styled("Home", GruvboxColor::teal().fg_style().to_hydrate()),
             Line::styled("Projects", GruvboxColor::teal().fg_style().to_hydrate()),
             Line::styled("Blog", GruvboxColor::teal().fg_style().to_hydrate()),
+            Line::styled("Archive", GruvboxColor::teal().fg_style().to_hydrate()),
         ];
         let tabs = Tabs::new(titles)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
                     .title(padded_title(
-                        "The Avid Rustacean".to_owned(),
+                        site::site().title,
                         GruvboxColor::burnt_orange().fg_style(),
                     ))
                     .title_alignment(Alignment::Center),
@@ -291,19 +542,29 @@ impl TermApp {
     }
 
     fn draw_footer(&self, rect: Rect, frame: &mut Frame<'_>) {
-        let line = Line::from(vec![
-            Span::styled("RSS", GruvboxColor::blue().fg_style().to_hydrate()),
-            Span::from(" | "),
-            Span::styled("Email", GruvboxColor::blue().fg_style().to_hydrate()),
-            Span::from(" | "),
-            Span::styled("Repo", GruvboxColor::blue().fg_style().to_hydrate()),
-            Span::from(" | "),
-            Span::styled("LinkedIn", GruvboxColor::blue().fg_style().to_hydrate()),
-            Span::from(" "),
-        ])
-        .alignment(Alignment::Right);
+        let site = site::site();
+        let names = ["Search (/)", "Atom", "JSON Feed"]
+            .map(String::from)
+            .into_iter()
+            .chain(site.links.into_iter().map(|link| link.name));
+        let mut spans = Vec::new();
+        for name in names {
+            if !spans.is_empty() {
+                spans.push(Span::from(" | "));
+            }
+            spans.push(Span::styled(
+                name,
+                GruvboxColor::blue().fg_style().to_hydrate(),
+            ));
+        }
+        spans.push(Span::from(" "));
+        let line = Line::from(spans).alignment(Alignment::Right);
+        let mut block = Block::default().borders(Borders::ALL);
+        if let Some(author) = site.author {
+            block = block.title(format!(" © {author} "));
+        }
         let tabs = Paragraph::new(line)
-            .block(Block::default().borders(Borders::ALL))
+            .block(block)
             .style(GruvboxColor::orange().fg_style());
         frame.render_widget(tabs, rect);
     }
@@ -321,6 +582,12 @@ impl From<BlogMessage> for TermAppMsg {
     }
 }
 
+impl From<ArchiveMessage> for TermAppMsg {
+    fn from(value: ArchiveMessage) -> Self {
+        Self::ComponentMsg(ComponentMsg::Archive(value))
+    }
+}
+
 impl From<PostMessage> for TermAppMsg {
     fn from(value: PostMessage) -> Self {
         Self::ComponentMsg(ComponentMsg::Post(value))
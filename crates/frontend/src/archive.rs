@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use avid_rustacean_model::Archive;
+use gloo_net::http::Request;
+use ratatui::{prelude::*, widgets::*};
+use webatui::prelude::*;
+use yew::Context;
+
+use crate::{
+    app::{AppBodyProps, Link, TermApp},
+    palette::{GruvboxColor, GruvboxExt},
+    site,
+    utils::{padded_title, ScrollRef},
+};
+
+/// Every post, listed under the year and month that it was published in.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ArchivePage {
+    lines: Vec<Line<'static>>,
+    /// Maps the title of each post to its name.
+    titles: HashMap<String, String>,
+}
+
+#[derive(Debug)]
+pub enum ArchiveMessage {
+    Archive(Archive),
+}
+
+impl ArchivePage {
+    pub fn setup(&self, ctx: &Context<WebTerminal<TermApp>>) {
+        ctx.link().send_future(async move {
+            let archive = match Request::get(&site::url("archive.json")).send().await {
+                Ok(resp) => resp.json().await.unwrap_or_default(),
+                Err(_) => Archive::default(),
+            };
+            WebTermMessage::new(ArchiveMessage::Archive(archive))
+        });
+    }
+
+    pub fn create() -> Self {
+        Self {
+            lines: Vec::new(),
+            titles: HashMap::new(),
+        }
+    }
+
+    pub fn link(&self, text: &str) -> Option<Link> {
+        let name = self.titles.get(text)?;
+        Some(Link::Page(AppBodyProps::Post(name.clone(), None)))
+    }
+
+    pub fn handle_scroll(&mut self, _dir: ScrollMotion) {}
+
+    pub fn update(&mut self, msg: ArchiveMessage) {
+        match msg {
+            ArchiveMessage::Archive(archive) => {
+                let count = |n: usize| match n {
+                    1 => "1 post".to_owned(),
+                    n => format!("{n} posts"),
+                };
+                self.lines.clear();
+                self.titles.clear();
+                for year in archive.years {
+                    self.lines.push(Line::from(vec![
+                        Span::styled(
+                            year.year.to_string(),
+                            GruvboxColor::yellow().fg_style().bold(),
+                        ),
+                        Span::styled(
+                            format!(" ({})", count(year.count)),
+                            GruvboxColor::dark_4().fg_style(),
+                        ),
+                    ]));
+                    for month in year.months {
+                        self.lines.push(Line::from(vec![
+                            Span::raw("  "),
+                            Span::styled(month.name, GruvboxColor::orange().fg_style().bold()),
+                            Span::styled(
+                                format!(" ({})", count(month.posts.len())),
+                                GruvboxColor::dark_4().fg_style(),
+                            ),
+                        ]));
+                        for post in month.posts {
+                            // Only the day is shown since the year and month are in the headers
+                            let day = post.create_on.get(8..10).unwrap_or_default().to_owned();
+                            self.lines.push(Line::from(vec![
+                                Span::raw("    "),
+                                Span::styled(day, GruvboxColor::default_fg().fg_style()),
+                                Span::raw("  "),
+                                Span::styled(
+                                    post.title.clone(),
+                                    GruvboxColor::teal()
+                                        .fg_style()
+                                        .to_hydrate()
+                                        .add_modifier(Modifier::UNDERLINED),
+                                ),
+                            ]));
+                            self.titles.insert(post.title, post.real_name);
+                        }
+                    }
+                    self.lines.push(Line::default());
+                }
+                self.lines.pop();
+            }
+        }
+    }
+
+    pub fn draw(&self, scroll: &ScrollRef, rect: Rect, frame: &mut Frame<'_>) {
+        let widget = Paragraph::new(self.lines.clone())
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::new()
+                    .title(padded_title(
+                        "Archive".into(),
+                        GruvboxColor::green().full_style(GruvboxColor::dark_4()),
+                    ))
+                    .borders(Borders::ALL)
+                    .padding(Padding::horizontal(1)),
+            );
+        scroll.set_content_length(widget.line_count(rect.width.saturating_sub(2)));
+        frame.render_widget(widget, rect);
+    }
+}
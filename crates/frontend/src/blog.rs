@@ -1,21 +1,24 @@
 use std::collections::{HashMap, HashSet};
 
-use avid_rustacean_model::PostSummary;
+use avid_rustacean_model::{PostPage, PostSummary};
 use gloo_net::http::Request;
 use ratatui::{prelude::*, widgets::*};
 use webatui::prelude::*;
 use yew::Context;
-use yew_router::prelude::*;
 
 use crate::{
-    app::{AppBodyProps, TermApp},
+    app::{AppBodyProps, Link, TermApp},
     palette::{GruvboxColor, GruvboxExt},
-    utils::{padded_title, render_markdown, MdLine, ScrollRef},
-    Route,
+    site,
+    utils::{link, padded_title, render_markdown, MdLine, ScrollRef},
 };
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Blog {
+    /// The page of the index that is shown, numbered from 1.
+    page: usize,
+    /// The number of pages in the index.
+    pages: usize,
     summaries: Vec<(PostSummary, Vec<Line<'static>>)>,
     titles: HashSet<String>,
     links: HashMap<String, String>,
@@ -24,23 +27,28 @@ pub struct Blog {
 
 #[derive(Debug)]
 pub enum BlogMessage {
-    PostSummaries(Vec<PostSummary>),
-    Clicked(String),
+    PostSummaries(PostPage),
 }
 
 impl Blog {
     pub fn setup(&self, ctx: &Context<WebTerminal<TermApp>>) {
+        let page = self.page;
         ctx.link().send_future(async move {
-            let summaries = match Request::get("/tui/posts.json").send().await {
+            let summaries = match Request::get(&site::url(&format!("posts-{page}.json")))
+                .send()
+                .await
+            {
                 Ok(resp) => resp.json().await.unwrap_or_default(),
-                Err(_) => Vec::new(),
+                Err(_) => PostPage::default(),
             };
             WebTermMessage::new(BlogMessage::PostSummaries(summaries))
         });
     }
 
-    pub fn create() -> Self {
+    pub fn create(page: usize) -> Self {
         Self {
+            page: page.max(1),
+            pages: 1,
             scroll: 0,
             summaries: Vec::new(),
             links: HashMap::new(),
@@ -48,24 +56,32 @@ impl Blog {
         }
     }
 
-    pub fn hydrate(&self, ctx: &Context<WebTerminal<TermApp>>, span: &mut DehydratedSpan) {
-        if let Some(link) = self.links.get(span.text()) {
-            span.hyperlink(link.clone())
-        } else if self.titles.contains(span.text()) {
-            let title = span.text().to_owned();
+    pub fn link(&self, text: &str) -> Option<Link> {
+        // The page controls are bracketed so that they can't be mistaken for a post's title
+        let page = match text {
+            "[<]" => Some(self.page - 1),
+            "[>]" => Some(self.page + 1),
+            text => text
+                .strip_prefix('[')
+                .and_then(|text| text.strip_suffix(']'))
+                .and_then(|page| page.parse().ok()),
+        };
+        if let Some(page) = page.filter(|page| (1..=self.pages).contains(page)) {
+            Some(Link::Page(AppBodyProps::Blog(page)))
+        } else if let Some(url) = self.links.get(text) {
+            Some(link(url))
+        } else if self.titles.contains(text) {
             let real_name = self
                 .summaries
                 .iter()
-                .find(|(summary, _)| summary.title == title)
+                .find(|(summary, _)| summary.title == text)
                 .unwrap()
                 .0
                 .real_name
                 .clone();
-            span.on_click(
-                ctx.link().callback(move |_| {
-                    WebTermMessage::new(BlogMessage::Clicked(real_name.clone()))
-                }),
-            );
+            Some(Link::Page(AppBodyProps::Post(real_name, None)))
+        } else {
+            None
         }
     }
 
@@ -76,41 +92,47 @@ impl Blog {
         }
     }
 
-    pub fn update(&mut self, ctx: TermContext<'_, TermApp>, msg: BlogMessage) {
-        match msg {
-            BlogMessage::PostSummaries(summaries) => {
-                self.summaries = summaries
+    /// The page to switch to when a key is pressed: the arrow keys and `[`/`]` move between pages.
+    pub fn handle_key(&self, key: &str) -> Option<AppBodyProps> {
+        let page = match key {
+            "ArrowLeft" | "[" => self.page - 1,
+            "ArrowRight" | "]" => self.page + 1,
+            _ => return None,
+        };
+        (1..=self.pages)
+            .contains(&page)
+            .then_some(AppBodyProps::Blog(page))
+    }
+
+    /// Shows a page of the index.
+    pub fn load(&mut self, page: PostPage) {
+        self.pages = page.pages.max(1);
+        self.summaries = page
+            .posts
+            .into_iter()
+            .map(|s| {
+                self.titles.insert(s.title.clone());
+                let lines = render_markdown(s.summary.clone(), &mut self.links)
                     .into_iter()
-                    .rev()
-                    .map(|s| {
-                        self.titles.insert(s.title.clone());
-                        let lines = render_markdown(s.summary.clone(), &mut self.links)
-                            .into_iter()
-                            .filter_map(|l| match l {
-                                MdLine::Plain(l) => Some(l.alignment(Alignment::Left)),
-                                MdLine::Code(_) => None,
-                            });
-                        (s, lines.collect())
-                    })
-                    .collect();
-            }
-            BlogMessage::Clicked(name) => {
-                let name = name.replace(' ', "-");
-                ctx.ctx()
-                    .link()
-                    .send_message(WebTermMessage::new(AppBodyProps::Post(name.clone())));
-                ctx.ctx()
-                    .link()
-                    .navigator()
-                    .unwrap()
-                    .push(&Route::Post { name });
-            }
+                    .filter_map(|l| match l {
+                        MdLine::Plain(l) => Some(l.alignment(Alignment::Left)),
+                        MdLine::Code(..) => None,
+                    });
+                (s, lines.collect())
+            })
+            .collect();
+    }
+
+    pub fn update(&mut self, msg: BlogMessage) {
+        match msg {
+            BlogMessage::PostSummaries(page) => self.load(page),
         }
     }
 
     pub fn draw(&self, scroll: &ScrollRef, rect: Rect, frame: &mut Frame<'_>) {
         let width = rect.width.saturating_sub(6) as usize;
-        let mut lines = Vec::with_capacity(5 * self.summaries.len() + 1);
+        let mut lines = Vec::with_capacity(5 * self.summaries.len() + 3);
+        lines.push(self.page_controls());
         lines.push(
             Line::styled(
                 "─".repeat(width),
@@ -144,6 +166,7 @@ impl Blog {
             )
             .alignment(Alignment::Center),
         );
+        lines.push(self.page_controls());
         let widget = Paragraph::new(lines)
             .alignment(Alignment::Center)
             .wrap(Wrap { trim: true })
@@ -158,4 +181,28 @@ impl Blog {
         scroll.set_content_length(widget.line_count(rect.width.saturating_sub(2)));
         frame.render_widget(widget, rect);
     }
+
+    /// The links to the other pages of the index, e.g. `[<] [1] [2] [3] [>]`. The current page is
+    /// highlighted instead of being a link.
+    fn page_controls(&self) -> Line<'static> {
+        let link = |text: String, enabled: bool| match enabled {
+            true => Span::styled(text, GruvboxColor::teal().fg_style().to_hydrate()),
+            false => Span::styled(text, GruvboxColor::dark_4().fg_style()),
+        };
+        let mut spans = vec![link("[<]".to_owned(), self.page > 1)];
+        for page in 1..=self.pages {
+            spans.push(Span::raw(" "));
+            if page == self.page {
+                spans.push(Span::styled(
+                    format!("[{page}]"),
+                    GruvboxColor::yellow().fg_style().bold(),
+                ));
+            } else {
+                spans.push(link(format!("[{page}]"), true));
+            }
+        }
+        spans.push(Span::raw(" "));
+        spans.push(link("[>]".to_owned(), self.page < self.pages));
+        Line::from(spans).alignment(Alignment::Center)
+    }
 }
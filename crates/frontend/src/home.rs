@@ -6,12 +6,13 @@ use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Padding, Paragraph, Wrap},
 };
-use webatui::{backend::DehydratedSpan, ScrollMotion, WebTermMessage, WebTerminal};
+use webatui::{ScrollMotion, WebTermMessage, WebTerminal};
 use yew::Context;
 
 use crate::{
-    app::{ComponentMsg, TermApp},
+    app::{ComponentMsg, Link, TermApp},
     palette::GruvboxExt,
+    site,
     utils::{padded_title, render_markdown, MdLine, ScrollRef},
 };
 
@@ -30,7 +31,7 @@ pub enum HomeMessage {
 impl Home {
     pub fn setup(&self, ctx: &Context<WebTerminal<TermApp>>) {
         ctx.link().send_future(async move {
-            let home = match Request::get("/tui/home.json").send().await {
+            let home = match Request::get(&site::url("home.json")).send().await {
                 Ok(resp) => resp.json().await.unwrap_or_default(),
                 Err(_) => HomePage::default(),
             };
@@ -54,7 +55,9 @@ impl Home {
         }
     }
 
-    pub fn hydrate(&self, _ctx: &Context<WebTerminal<TermApp>>, _span: &mut DehydratedSpan) {}
+    pub fn link(&self, _text: &str) -> Option<Link> {
+        None
+    }
 
     pub fn update(&mut self, msg: HomeMessage) {
         match msg {
@@ -63,7 +66,7 @@ impl Home {
                     .into_iter()
                     .filter_map(|l| match l {
                         MdLine::Plain(l) => Some(l),
-                        MdLine::Code(_) => None,
+                        MdLine::Code(..) => None,
                     })
                     .collect();
                 self.data = Paragraph::new(lines)
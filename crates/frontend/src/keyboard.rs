@@ -0,0 +1,54 @@
+use std::cell::{Cell, RefCell};
+
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::KeyboardEvent;
+use yew::Callback;
+
+/// Listens for key presses anywhere on the page and passes along the name of the key (e.g. `a`,
+/// `/`, or `ArrowDown`). Webatui only handles the mouse, so the listener is put on the window
+/// directly.
+///
+/// Keys that are pressed along with Ctrl, Alt, or Meta are left to the browser. While
+/// [`capture`] is on, the browser doesn't see any other keys either, which keeps typing in the
+/// search prompt from triggering find-as-you-type or scrolling the page. Otherwise, only `/` is
+/// kept from the browser.
+///
+/// Like [`crate::live_reload::connect`], only the first call adds the listener. Later calls
+/// replace who is told about key presses.
+pub fn listen(on_key: Callback<String>) {
+    ON_KEY.with(|cb| *cb.borrow_mut() = Some(on_key));
+    if LISTENING.with(|listening| listening.replace(true)) {
+        return;
+    }
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let on_keydown = Closure::<dyn FnMut(KeyboardEvent)>::new(|event: KeyboardEvent| {
+        if event.ctrl_key() || event.alt_key() || event.meta_key() {
+            return;
+        }
+        let key = event.key();
+        if CAPTURE.with(Cell::get) || key == "/" {
+            event.prevent_default();
+        }
+        ON_KEY.with(|cb| {
+            if let Some(cb) = cb.borrow().as_ref() {
+                cb.emit(key)
+            }
+        });
+    });
+    window.set_onkeydown(Some(on_keydown.as_ref().unchecked_ref()));
+    // The handler lives for as long as the page does
+    on_keydown.forget();
+}
+
+/// Sets whether every key press is kept from the browser. See [`listen`].
+pub fn capture(capture: bool) {
+    CAPTURE.with(|c| c.set(capture));
+}
+
+thread_local! {
+    static LISTENING: Cell<bool> = const { Cell::new(false) };
+    static CAPTURE: Cell<bool> = const { Cell::new(false) };
+    static ON_KEY: RefCell<Option<Callback<String>>> = const { RefCell::new(None) };
+}
@@ -0,0 +1,47 @@
+#![warn(rust_2018_idioms)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    unreachable_pub,
+    unreachable_patterns,
+    unused,
+    unused_qualifications,
+    while_true,
+    trivial_casts,
+    trivial_bounds,
+    trivial_numeric_casts,
+    unconditional_panic,
+    clippy::all
+)]
+
+//! The pages of the TUI and the app that switches between them. The binary runs the app in the
+//! browser, and the pages can also be drawn elsewhere (see [`app::PageData`]).
+
+use yew_router::prelude::*;
+
+pub mod app;
+pub mod archive;
+pub mod blog;
+pub mod home;
+pub mod keyboard;
+pub mod live_reload;
+pub mod palette;
+pub mod posts;
+pub mod project;
+pub mod search;
+pub mod site;
+pub mod utils;
+
+/// The routes of the TUI, relative to its base path (see [`site::base_path`]).
+#[derive(Debug, Clone, Routable, PartialEq)]
+pub enum Route {
+    #[at("/")]
+    Home,
+    #[at("/projects")]
+    AllProjects,
+    #[at("/blog")]
+    Blog,
+    #[at("/archive")]
+    Archive,
+    #[at("/blog/:name")]
+    Post { name: String },
+}
@@ -0,0 +1,60 @@
+use std::cell::{Cell, RefCell};
+
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{MessageEvent, WebSocket};
+use yew::Callback;
+
+/// When the site is served by `builder serve`, the page is marked with a `live-reload` meta tag
+/// that holds the path of a websocket. The builder sends a message over it whenever something
+/// changes. If the content changed, the current page refetches its data. If the frontend itself
+/// was rebuilt, the whole page is reloaded. On the deployed site, there is no meta tag and nothing
+/// happens.
+///
+/// The terminal is set up again whenever the route changes, so only the first call opens the
+/// websocket. Later calls replace who is told about changes.
+pub fn connect(on_reload: Callback<()>) {
+    ON_RELOAD.with(|cb| *cb.borrow_mut() = Some(on_reload));
+    if CONNECTED.with(|connected| connected.replace(true)) {
+        return;
+    }
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let Some(path) = window
+        .document()
+        .and_then(|doc| doc.query_selector(r#"meta[name="live-reload"]"#).ok()?)
+        .and_then(|meta| meta.get_attribute("content"))
+    else {
+        return;
+    };
+    let location = window.location();
+    let (Ok(protocol), Ok(host)) = (location.protocol(), location.host()) else {
+        return;
+    };
+    let scheme = if protocol == "https:" { "wss" } else { "ws" };
+    let Ok(socket) = WebSocket::new(&format!("{scheme}://{host}{path}")) else {
+        return;
+    };
+    let on_message =
+        Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            match event.data().as_string().as_deref() {
+                Some("frontend") => {
+                    let _ = location.reload();
+                }
+                _ => ON_RELOAD.with(|cb| {
+                    if let Some(cb) = cb.borrow().as_ref() {
+                        cb.emit(())
+                    }
+                }),
+            }
+        });
+    socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    // The socket and its handler live for as long as the page does
+    on_message.forget();
+    std::mem::forget(socket);
+}
+
+thread_local! {
+    static CONNECTED: Cell<bool> = const { Cell::new(false) };
+    static ON_RELOAD: RefCell<Option<Callback<()>>> = const { RefCell::new(None) };
+}
@@ -13,9 +13,10 @@
     clippy::all
 )]
 
-use std::fmt::Debug;
-
-use app::{AppBodyProps, TermApp};
+use avid_rustacean_frontend::{
+    app::{AppBodyProps, TermApp},
+    site, Route,
+};
 use base16_palettes::{
     palettes::{GruvboxDarkHard, GruvboxPalette},
     Palette,
@@ -24,32 +25,13 @@ use webatui::{WebTermProps, WebTerminal};
 use yew::{function_component, html, Html};
 use yew_router::prelude::*;
 
-pub mod app;
-pub mod blog;
-pub mod home;
-pub mod palette;
-pub mod posts;
-pub mod project;
-pub mod utils;
-
-#[derive(Debug, Clone, Routable, PartialEq)]
-enum Route {
-    #[at("/tui/")]
-    Home,
-    #[at("/tui/projects")]
-    AllProjects,
-    #[at("/tui/blog")]
-    Blog,
-    #[at("/tui/blog/:name")]
-    Post { name: String },
-}
-
 fn switch(route: Route) -> Html {
     let body = match route {
         Route::Home => AppBodyProps::Home,
         Route::AllProjects => AppBodyProps::AllProjects,
-        Route::Blog => AppBodyProps::Blog,
-        Route::Post { name } => AppBodyProps::Post(name),
+        Route::Blog => AppBodyProps::Blog(current_page()),
+        Route::Archive => AppBodyProps::Archive,
+        Route::Post { name } => AppBodyProps::Post(name, current_anchor()),
     };
     let inner = TermApp::new(body);
     let props = WebTermProps::new_with_palette(
@@ -59,17 +41,44 @@ fn switch(route: Route) -> Html {
     html! { <WebTerminal<TermApp> ..props /> }
 }
 
+/// The anchor in the current URL (without the leading `#`), if there is one.
+fn current_anchor() -> Option<String> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    let anchor = hash.trim_start_matches('#');
+    (!anchor.is_empty()).then(|| anchor.to_owned())
+}
+
+/// The page of the blog index in the current URL's `?page=` query, which defaults to the first.
+fn current_page() -> usize {
+    web_sys::window()
+        .and_then(|window| window.location().search().ok())
+        .and_then(|search| {
+            search
+                .trim_start_matches('?')
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("page=")?.parse().ok())
+        })
+        .unwrap_or(1)
+}
+
 #[function_component]
 #[allow(non_snake_case)]
 fn App() -> Html {
     html! {
-        <BrowserRouter>
+        <BrowserRouter basename={site::base_path()}>
             <Switch<Route> render={switch} />
         </BrowserRouter>
     }
 }
 
 fn main() {
+    // The builder prerenders each page as a placeholder until the app is running
+    if let Some(snapshot) = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|doc| doc.get_element_by_id("tui-snapshot"))
+    {
+        snapshot.remove();
+    }
     // Render the app
     yew::Renderer::<App>::new().render();
 }
@@ -1,7 +1,40 @@
+use std::cell::RefCell;
+
+use gloo_net::http::Request;
 use ratatui::style::{Color, Style};
+use webatui::{WebTermMessage, WebTerminal};
+use yew::Context;
+
+use crate::{
+    app::{TermApp, TermAppMsg},
+    site,
+};
 
 pub use avid_rustacean_model::*;
 
+thread_local! {
+    /// The code theme generated by the builder. Any token class that it does not cover uses the
+    /// default colors from `TokenClassExt`.
+    static CODE_THEME: RefCell<CodeTheme> = RefCell::default();
+}
+
+/// Replaces the theme used to color highlighted code.
+pub fn set_code_theme(theme: CodeTheme) {
+    CODE_THEME.with_borrow_mut(|t| *t = theme);
+}
+
+/// Fetches the code theme, which is used by every page with code on it. The app is told once it
+/// arrives. If the builder wasn't given a theme, there is no file and the defaults are used.
+pub fn load_code_theme(ctx: &Context<WebTerminal<TermApp>>) {
+    ctx.link().send_future(async move {
+        let theme = match Request::get(&site::url("code-theme.json")).send().await {
+            Ok(resp) => resp.json().await.unwrap_or_default(),
+            Err(_) => CodeTheme::default(),
+        };
+        WebTermMessage::new(TermAppMsg::CodeTheme(theme))
+    });
+}
+
 /// A simple trait to extend the iterface of GruvboxColor.
 pub trait GruvboxExt {
     // TODO: Const-ify these when possible.
@@ -75,6 +108,38 @@ impl GruvboxExt for GruvboxColor {
     }
 }
 
+/// Maps the semantic classes of highlighted code onto the palette.
+pub trait TokenClassExt {
+    fn color(self) -> GruvboxColor;
+    fn code_style(self, bg: GruvboxColor) -> Style;
+}
+
+impl TokenClassExt for TokenClass {
+    fn color(self) -> GruvboxColor {
+        if let Some(color) = CODE_THEME.with_borrow(|t| t.get(self)) {
+            return color;
+        }
+        match self {
+            TokenClass::Plain | TokenClass::Operator | TokenClass::Punctuation => {
+                GruvboxColor::default_fg()
+            }
+            TokenClass::Keyword => GruvboxColor::red(),
+            TokenClass::Type => GruvboxColor::yellow(),
+            TokenClass::Function => GruvboxColor::green(),
+            TokenClass::Macro => GruvboxColor::teal(),
+            TokenClass::Lifetime => GruvboxColor::burnt_orange(),
+            TokenClass::String => GruvboxColor::green(),
+            TokenClass::Number | TokenClass::Constant => GruvboxColor::pink(),
+            TokenClass::Comment => GruvboxColor::orange(),
+            TokenClass::Attribute => GruvboxColor::blue(),
+        }
+    }
+
+    fn code_style(self, bg: GruvboxColor) -> Style {
+        self.color().full_style(bg)
+    }
+}
+
 pub const fn indexed_gruvbox(i: u8) -> GruvboxColor {
     match i {
         0 => GruvboxColor::Neutral(GruvboxNeutral::Dark(Shade::Darkest)),
@@ -124,6 +189,14 @@ pub const fn indexed_color_str(i: u8) -> &'static str {
     }
 }
 
+/// The color at an index of the palette as RGB, for when it is drawn somewhere that has its own
+/// palette (like a real terminal).
+pub fn indexed_color_rgb(i: u8) -> Color {
+    let hex = indexed_color_str(i).trim_start_matches('#');
+    let channel = |at: usize| u8::from_str_radix(&hex[at..at + 2], 16).unwrap();
+    Color::Rgb(channel(0), channel(2), channel(4))
+}
+
 /*
 // Darks
 const BASE_0_RGB: Color = Color::Rgb(29, 32, 33);
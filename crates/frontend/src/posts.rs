@@ -1,11 +1,13 @@
+use avid_rustacean_model::{post_slug, Aliases};
 use gloo_net::http::Request;
 use ratatui::prelude::*;
 use serde::Deserialize;
-use webatui::{backend::DehydratedSpan, ScrollMotion, WebTermMessage, WebTerminal};
+use webatui::{ScrollMotion, WebTermMessage, WebTerminal};
 use yew::prelude::*;
 
 use crate::{
-    app::TermApp,
+    app::{AppBodyProps, Link, TermApp, TermAppMsg},
+    site,
     utils::{Markdown, ScrollRef},
 };
 
@@ -14,6 +16,8 @@ pub struct Post {
     title: String,
     real_name: String,
     body: Markdown,
+    /// The anchor of the heading to jump to once the post is loaded.
+    anchor: Option<String>,
     scroll: u16,
 }
 
@@ -29,25 +33,35 @@ pub struct PostData {
 
 impl Post {
     pub fn setup(&self, ctx: &Context<WebTerminal<TermApp>>) {
-        let cp_name = self.real_name.clone().replace(' ', "-");
+        let cp_name = self.real_name.clone();
+        let anchor = self.anchor.clone();
         ctx.link().send_future(async move {
-            let post = match Request::get(&format!("/tui/posts/{cp_name}.json"))
+            let post = match Request::get(&site::url(&format!("posts/{cp_name}.json")))
                 .send()
                 .await
             {
-                Ok(resp) => resp.json().await.unwrap_or_default(),
-                Err(_) => avid_rustacean_model::Post::default(),
+                Ok(resp) if resp.ok() => resp.json().await.ok(),
+                _ => None,
             };
-            WebTermMessage::new(PostMessage::Post(post))
+            match post {
+                Some(post) => WebTermMessage::new(PostMessage::Post(post)),
+                // The post might have been renamed, in which case its old name is an alias
+                None => match alias(&cp_name).await {
+                    Some(name) => {
+                        WebTermMessage::new(TermAppMsg::Moved(AppBodyProps::Post(name, anchor)))
+                    }
+                    None => WebTermMessage::new(PostMessage::Post(Default::default())),
+                },
+            }
         });
     }
 
-    pub fn create(name: String) -> Self {
-        let mut real_name = String::with_capacity(name.len());
-        url_escape::decode_to_string(name, &mut real_name);
+    pub fn create(name: String, anchor: Option<String>) -> Self {
+        let real_name = post_slug(&name);
         Self {
             title: String::new(),
             body: Markdown::default(),
+            anchor,
             scroll: 0,
             real_name,
         }
@@ -57,8 +71,8 @@ impl Post {
         None
     }
 
-    pub fn hydrate(&self, ctx: &Context<WebTerminal<TermApp>>, span: &mut DehydratedSpan) {
-        self.body.hydrate(ctx, span)
+    pub fn link(&self, text: &str) -> Option<Link> {
+        self.body.link(text)
     }
 
     pub fn handle_scroll(&mut self, dir: ScrollMotion) {
@@ -73,6 +87,9 @@ impl Post {
             PostMessage::Post(post) => {
                 self.title = post.summary.title.clone();
                 self.body = Markdown::new(post.summary.title.clone(), post.body);
+                if let Some(anchor) = &self.anchor {
+                    self.body.jump_to(anchor);
+                }
             }
         }
     }
@@ -81,3 +98,10 @@ impl Post {
         self.body.draw(scroll, rect, frame)
     }
 }
+
+/// The name of the post that an old name leads to, if it is one of the post's aliases.
+async fn alias(name: &str) -> Option<String> {
+    let resp = Request::get(&site::url("aliases.json")).send().await.ok()?;
+    let mut aliases: Aliases = resp.json().await.ok()?;
+    aliases.remove(name)
+}
@@ -6,9 +6,10 @@ use webatui::prelude::*;
 use yew::Context;
 
 use crate::{
-    app::TermApp,
+    app::{Link, TermApp},
     palette::{GruvboxColor, GruvboxExt},
-    utils::{padded_title, render_markdown, MdLine, ScrollRef},
+    site,
+    utils::{link, padded_title, render_markdown, MdLine, ScrollRef},
 };
 
 #[derive(Debug, PartialEq, Clone)]
@@ -25,7 +26,7 @@ pub enum AllProjectsMessage {
 impl AllProjects {
     pub fn setup(&self, ctx: &Context<WebTerminal<TermApp>>) {
         ctx.link().send_future(async move {
-            let projects = match Request::get("/tui/projects.json").send().await {
+            let projects = match Request::get(&site::url("projects.json")).send().await {
                 Ok(resp) => resp.json().await.unwrap_or_default(),
                 Err(_) => Default::default(),
             };
@@ -41,22 +42,20 @@ impl AllProjects {
         }
     }
 
-    pub fn hydrate(&self, _ctx: &Context<WebTerminal<TermApp>>, span: &mut DehydratedSpan) {
-        if let Some(link) = self.links.get(span.text()) {
-            span.hyperlink(link.clone());
-        }
+    pub fn link(&self, text: &str) -> Option<Link> {
+        self.links.get(text).map(|url| link(url))
     }
 
     pub fn handle_scroll(&mut self, _dir: ScrollMotion) {}
 
-    pub fn update(&mut self, _ctx: TermContext<'_, TermApp>, msg: AllProjectsMessage) {
+    pub fn update(&mut self, msg: AllProjectsMessage) {
         match msg {
             AllProjectsMessage::ProjectSummaries(projects) => {
                 let projects = render_markdown(projects, &mut self.links)
                     .into_iter()
                     .filter_map(|l| match l {
                         MdLine::Plain(l) => Some(l.alignment(Alignment::Center)),
-                        MdLine::Code(_) => None,
+                        MdLine::Code(..) => None,
                     })
                     .collect();
                 self.projects = projects;
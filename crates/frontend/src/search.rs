@@ -0,0 +1,246 @@
+use std::{cell::RefCell, rc::Rc};
+
+use avid_rustacean_model::SearchIndex;
+use gloo_net::http::Request;
+use ratatui::{prelude::*, widgets::*};
+use webatui::{prelude::*, WebTermMessage, WebTerminal};
+use yew::Context;
+
+use crate::{
+    app::{AppBodyProps, Link, TermApp},
+    keyboard,
+    palette::{GruvboxColor, GruvboxExt},
+    site,
+    utils::padded_title,
+};
+
+/// The most results that are shown at once.
+const MAX_RESULTS: usize = 20;
+
+/// The search prompt, which is opened with `/` and drawn over the body. Results are ranked by the
+/// index built by the builder, and each one links to the heading where its match is.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Search {
+    query: String,
+    results: Vec<SearchResult>,
+    selected: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SearchResult {
+    /// The text of the link to the result, e.g. `Post Title › Heading`.
+    label: String,
+    post: String,
+    anchor: Option<String>,
+    snippet: Vec<(String, bool)>,
+}
+
+#[derive(Debug)]
+pub enum SearchMessage {
+    Loaded(SearchIndex),
+}
+
+/// What the app should do after the prompt handled a key press.
+pub enum SearchAction {
+    /// Nothing, other than redraw the prompt.
+    Redraw,
+    Close,
+    Open(AppBodyProps),
+}
+
+impl Search {
+    /// Opens the prompt, fetching the search index the first time that search is used.
+    pub fn open(ctx: &Context<WebTerminal<TermApp>>) -> Self {
+        keyboard::capture(true);
+        if INDEX.with(|index| index.borrow().is_none()) {
+            ctx.link().send_future(async move {
+                let index = match Request::get(&site::url("search.json")).send().await {
+                    Ok(resp) => resp.json().await.unwrap_or_default(),
+                    Err(_) => SearchIndex::default(),
+                };
+                WebTermMessage::new(SearchMessage::Loaded(index))
+            });
+        }
+        Self::default()
+    }
+
+    /// Stops capturing keys. This needs to be called when the prompt is closed.
+    pub fn close(self) {
+        keyboard::capture(false);
+    }
+
+    /// Handles a message for the prompt, if it is still open.
+    pub fn update(search: Option<&mut Self>, msg: SearchMessage) {
+        match msg {
+            SearchMessage::Loaded(index) => {
+                INDEX.with(|i| *i.borrow_mut() = Some(Rc::new(index)));
+                if let Some(search) = search {
+                    search.refresh();
+                }
+            }
+        }
+    }
+
+    pub fn handle_key(&mut self, key: &str) -> SearchAction {
+        match key {
+            "Escape" => return SearchAction::Close,
+            "Enter" => {
+                if let Some(result) = self.results.get(self.selected) {
+                    return SearchAction::Open(result.props());
+                }
+            }
+            "ArrowDown" => {
+                self.selected = (self.selected + 1).min(self.results.len().saturating_sub(1))
+            }
+            "ArrowUp" => self.selected = self.selected.saturating_sub(1),
+            "Backspace" => {
+                self.query.pop();
+                self.refresh();
+            }
+            // Anything else with a longer name, like `Shift` or `Tab`, is not typed
+            key if key.chars().count() == 1 => {
+                self.query.push_str(key);
+                self.refresh();
+            }
+            _ => {}
+        }
+        SearchAction::Redraw
+    }
+
+    fn refresh(&mut self) {
+        let Some(index) = INDEX.with(|index| index.borrow().clone()) else {
+            return;
+        };
+        self.selected = 0;
+        self.results = index
+            .search(&self.query)
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|hit| SearchResult {
+                label: match &hit.section.heading {
+                    Some(heading) => format!("{} › {heading}", hit.section.title),
+                    None => hit.section.title.clone(),
+                },
+                post: hit.section.post.clone(),
+                anchor: hit.section.anchor(),
+                snippet: hit.snippet,
+            })
+            .collect();
+    }
+
+    pub fn link(&self, text: &str) -> Option<Link> {
+        let result = self.results.iter().find(|r| r.label == text)?;
+        Some(Link::Page(result.props()))
+    }
+
+    pub fn draw(&self, chunk: Rect, frame: &mut Frame<'_>) {
+        let width = chunk.width.saturating_sub(4).min(100);
+        let area = Rect {
+            x: chunk.x + (chunk.width - width) / 2,
+            y: chunk.y + 1,
+            width,
+            height: chunk.height.saturating_sub(2),
+        };
+        let block = Block::new()
+            .title(padded_title(
+                "Search".to_owned(),
+                GruvboxColor::yellow()
+                    .full_style(GruvboxColor::dark_3())
+                    .bold(),
+            ))
+            .title_alignment(Alignment::Center)
+            .borders(Borders::all())
+            .border_style(GruvboxColor::pink().fg_style())
+            .style(GruvboxColor::default_style());
+        let inner = block.inner(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::new(
+            Direction::Vertical,
+            [Constraint::Length(2), Constraint::Percentage(100)],
+        )
+        .split(inner);
+        let status = if INDEX.with(|index| index.borrow().is_none()) {
+            "Loading the index…".to_owned()
+        } else if self.query.trim().is_empty() {
+            "Type to search every post. Esc closes, ↑/↓ select, Enter opens.".to_owned()
+        } else {
+            match self.results.len() {
+                0 => "No results".to_owned(),
+                1 => "1 result".to_owned(),
+                n => format!("{n} results"),
+            }
+        };
+        let prompt = vec![
+            Line::from(vec![
+                Span::styled("/ ", GruvboxColor::orange().fg_style().bold()),
+                Span::styled(self.query.clone(), GruvboxColor::light_1().fg_style()),
+                Span::styled("█", GruvboxColor::orange().fg_style()),
+            ]),
+            Line::styled(status, GruvboxColor::dark_4().fg_style()),
+        ];
+        frame.render_widget(Paragraph::new(prompt), chunks[0]);
+
+        // Every result takes up three lines: its link, its snippet, and a gap
+        let width = chunks[1].width.saturating_sub(2) as usize;
+        let mut lines = Vec::with_capacity(self.results.len() * 3);
+        for (i, result) in self.results.iter().enumerate() {
+            let selected = i == self.selected;
+            let marker = if selected { "▶ " } else { "  " };
+            let mut label = GruvboxColor::teal().fg_style().bold().to_hydrate();
+            if selected {
+                label = label.bg(GruvboxColor::dark_2().to_color());
+            }
+            lines.push(Line::from(vec![
+                Span::styled(marker, GruvboxColor::orange().fg_style()),
+                Span::styled(result.label.clone(), label),
+            ]));
+            let mut snippet = vec![Span::from("  ")];
+            snippet.extend(
+                truncate(&result.snippet, width)
+                    .into_iter()
+                    .map(|(text, matched)| match matched {
+                        true => Span::styled(text, GruvboxColor::yellow().fg_style().bold()),
+                        false => Span::styled(text, GruvboxColor::default_fg().fg_style()),
+                    }),
+            );
+            lines.push(Line::from(snippet));
+            lines.push(Line::default());
+        }
+        let visible = chunks[1].height as usize;
+        let offset = (self.selected * 3 + 3).saturating_sub(visible);
+        frame.render_widget(Paragraph::new(lines).scroll((offset as u16, 0)), chunks[1]);
+    }
+}
+
+impl SearchResult {
+    fn props(&self) -> AppBodyProps {
+        AppBodyProps::Post(self.post.clone(), self.anchor.clone())
+    }
+}
+
+/// Cuts the pieces of a snippet down to the given number of characters so that each snippet fits
+/// on one line.
+fn truncate(snippet: &[(String, bool)], width: usize) -> Vec<(String, bool)> {
+    let mut left = width;
+    let mut digest = Vec::with_capacity(snippet.len());
+    for (text, matched) in snippet {
+        let len = text.chars().count();
+        if len <= left {
+            left -= len;
+            digest.push((text.clone(), *matched));
+        } else {
+            let mut text: String = text.chars().take(left.saturating_sub(1)).collect();
+            text.push('…');
+            digest.push((text, *matched));
+            break;
+        }
+    }
+    digest
+}
+
+thread_local! {
+    /// The search index is fetched once and kept for as long as the page is open.
+    static INDEX: RefCell<Option<Rc<SearchIndex>>> = const { RefCell::new(None) };
+}
@@ -0,0 +1,68 @@
+use std::cell::{OnceCell, RefCell};
+
+use avid_rustacean_model::SiteInfo;
+use gloo_net::http::Request;
+use webatui::{WebTermMessage, WebTerminal};
+use yew::Context;
+
+use crate::app::{TermApp, TermAppMsg};
+
+/// The base path that is used when the page doesn't have one, e.g. under `trunk serve`.
+const DEFAULT_BASE_PATH: &str = "/tui";
+
+/// The path that the TUI is served under, e.g. `/tui`. The builder marks every copy of
+/// `index.html` with a `tui-base` meta tag that holds it, which follows the site's base URL.
+/// Outside of the browser, there is no page, so the path comes from the site's config instead.
+pub fn base_path() -> String {
+    if !cfg!(target_arch = "wasm32") {
+        let path = site().base_path;
+        return match path.is_empty() {
+            true => DEFAULT_BASE_PATH.to_owned(),
+            false => path.trim_end_matches('/').to_owned(),
+        };
+    }
+    BASE_PATH.with(|path| {
+        path.get_or_init(|| {
+            web_sys::window()
+                .and_then(|window| window.document())
+                .and_then(|doc| doc.query_selector(r#"meta[name="tui-base"]"#).ok()?)
+                .and_then(|meta| meta.get_attribute("content"))
+                .map(|path| path.trim_end_matches('/').to_owned())
+                .unwrap_or_else(|| DEFAULT_BASE_PATH.to_owned())
+        })
+        .clone()
+    })
+}
+
+/// The URL of one of the TUI's files, e.g. `home.json`.
+pub fn url(file: &str) -> String {
+    format!("{}/{file}", base_path())
+}
+
+/// Fetches the site's config, unless it already has been. The app is told once it arrives.
+pub fn load(ctx: &Context<WebTerminal<TermApp>>) {
+    if SITE.with_borrow(|site| site.is_some()) {
+        return;
+    }
+    ctx.link().send_future(async move {
+        let site = match Request::get(&url("site.json")).send().await {
+            Ok(resp) => resp.json().await.unwrap_or_default(),
+            Err(_) => SiteInfo::default(),
+        };
+        WebTermMessage::new(TermAppMsg::Site(site))
+    });
+}
+
+pub fn set_site(site: SiteInfo) {
+    SITE.with_borrow_mut(|s| *s = Some(site));
+}
+
+/// The site's config. Until it has been fetched, everything is empty.
+pub fn site() -> SiteInfo {
+    SITE.with_borrow(|site| site.clone().unwrap_or_default())
+}
+
+thread_local! {
+    static BASE_PATH: OnceCell<String> = const { OnceCell::new() };
+    static SITE: RefCell<Option<SiteInfo>> = const { RefCell::new(None) };
+}
@@ -1,14 +1,18 @@
 use std::{cell::RefCell, collections::HashMap};
 
-use avid_rustacean_model::{GruvboxColor, MdNode, ParsedCode};
+use avid_rustacean_model::{slugify, GruvboxColor, MdNode, ParsedCode};
 use ratatui::{
+    buffer::Buffer,
     prelude::*,
     widgets::{block::Title, *},
 };
 use webatui::prelude::*;
-use yew::Context;
 
-use crate::{app::TermApp, palette::GruvboxExt};
+use crate::{
+    app::{AppBodyProps, Link},
+    palette::{GruvboxExt, TokenClassExt},
+    site,
+};
 
 /// A container for managing the logic for a well-formated scroll bar.
 #[derive(Debug, Clone, PartialEq)]
@@ -61,6 +65,11 @@ impl ScrollRef {
         *self.state.borrow_mut() = state;
     }
 
+    /// The number of lines of content, as of the last time that it was drawn.
+    pub fn content_length(&self) -> usize {
+        *self.content_length.borrow()
+    }
+
     /// Gets the scroll index.
     pub fn view_start(&self) -> usize {
         *self.view_start.borrow()
@@ -105,30 +114,35 @@ pub struct Markdown {
     lines: Vec<MdLine>,
     /// Any links contained within the document
     links: HashMap<String, String>,
+    /// The line that each heading starts on, keyed by the heading's anchor
+    anchors: HashMap<String, usize>,
+    /// A line that the view should jump to the next time that it is drawn
+    jump: RefCell<Option<usize>>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum MdLine {
     Plain(Line<'static>),
-    Code(Line<'static>),
+    /// A line of code and its background color, which is used to pad the line to the full width.
+    Code(Line<'static>, GruvboxColor),
 }
 
 impl MdLine {
     fn as_line(&self, width: usize) -> Line<'static> {
         match self {
             MdLine::Plain(line) => line.clone(),
-            MdLine::Code(code) => {
+            MdLine::Code(code, bg) => {
                 let mut code = code.clone();
                 let len = code.spans.iter().fold(0, |acc, s| acc + s.width());
                 if len == 0 {
                     code.spans = vec![
-                        Span::styled(" ".repeat(width), GruvboxColor::dark_3().bg_style()),
-                        Span::styled("\u{200b}", GruvboxColor::dark_3().bg_style()),
+                        Span::styled(" ".repeat(width), bg.bg_style()),
+                        Span::styled("\u{200b}", bg.bg_style()),
                     ];
                 } else {
                     code.spans.push(Span::styled(
                         " ".repeat(width.saturating_sub(len % width)),
-                        GruvboxColor::dark_3().bg_style(),
+                        bg.bg_style(),
                     ));
                 }
                 code
@@ -140,20 +154,51 @@ impl MdLine {
 impl Markdown {
     pub fn new(title: String, md: avid_rustacean_model::Markdown) -> Self {
         let mut links = HashMap::new();
-        let widgets = render_markdown(md, &mut links);
+        let mut anchors = HashMap::new();
+        let widgets = render_markdown_with_anchors(md, &mut links, &mut anchors);
         Self {
             lines: widgets,
             links,
+            anchors,
+            jump: RefCell::new(None),
             title,
         }
     }
 
-    pub fn hydrate(&self, _ctx: &Context<WebTerminal<TermApp>>, span: &mut DehydratedSpan) {
-        if let Some(link) = self.links.get(span.text()) {
-            span.hyperlink(link.clone());
+    /// Scrolls to the heading with the given anchor the next time that the document is drawn.
+    pub fn jump_to(&self, anchor: &str) {
+        if let Some(line) = self.anchors.get(anchor) {
+            *self.jump.borrow_mut() = Some(*line);
         }
     }
 
+    /// Where the link with the given text leads, if there is one.
+    pub fn link(&self, text: &str) -> Option<Link> {
+        self.links.get(text).map(|url| link(url))
+    }
+
+    /// The links in the document, keyed by their text.
+    pub fn links(&self) -> &HashMap<String, String> {
+        &self.links
+    }
+
+    /// Draws the whole document, headed by its title, into a buffer that is just tall enough to
+    /// fit it. Unlike in the TUI, there is no border and no scrolling, which is how the document is
+    /// read outside of the TUI (e.g. as text in a terminal).
+    pub fn render(&self, width: u16) -> Buffer {
+        let mut lines =
+            vec![
+                Line::styled(self.title.clone(), GruvboxColor::yellow().fg_style().bold())
+                    .alignment(Alignment::Center),
+            ];
+        lines.extend(self.lines.iter().map(|l| l.as_line(width as usize)));
+        let para = Paragraph::new(lines).wrap(Wrap { trim: false });
+        let area = Rect::new(0, 0, width, para.line_count(width) as u16);
+        let mut buffer = Buffer::empty(area);
+        para.render(area, &mut buffer);
+        buffer
+    }
+
     fn get_para(&self, width: usize) -> Paragraph<'static> {
         let lines: Vec<_> = self.lines.iter().map(|l| l.as_line(width)).collect();
         Paragraph::new(lines)
@@ -194,19 +239,58 @@ impl Markdown {
             )
             .split(rect)
         };
-        let para = self.get_para(chunks[1].width.saturating_sub(2) as usize);
-        scroll.set_content_length(para.line_count(chunks[1].width.saturating_sub(2)));
+        let width = chunks[1].width.saturating_sub(2);
+        let para = self.get_para(width as usize);
+        scroll.set_content_length(para.line_count(width));
+        if let Some(line) = self.jump.take() {
+            // Lines wrap, so the number of lines above the heading depends on the width
+            let above: Vec<_> = self.lines[..line]
+                .iter()
+                .map(|l| l.as_line(width as usize))
+                .collect();
+            let offset = Paragraph::new(above)
+                .wrap(Wrap { trim: false })
+                .line_count(width);
+            scroll.set_view_start(offset);
+        }
         let view_start = scroll.view_start();
         frame.render_widget(para.scroll((view_start as u16, 0)), chunks[1]);
     }
 }
 
+/// Where a link in a document leads. Links to posts (`/tui/blog/<name>`, with an optional
+/// `#anchor`) are routed within the app. Everything else is a normal hyperlink.
+pub fn link(url: &str) -> Link {
+    let posts = format!("{}/blog/", site::base_path());
+    match url.strip_prefix(&posts) {
+        Some(route) => {
+            let (name, anchor) = match route.split_once('#') {
+                Some((name, anchor)) => (name, Some(anchor.to_owned())),
+                None => (route, None),
+            };
+            Link::Page(AppBodyProps::Post(name.to_owned(), anchor))
+        }
+        None => Link::Url(url.to_owned()),
+    }
+}
+
 /// Renders an markdown document and returns the number of lines needed to display it
 pub fn render_markdown(
     md: avid_rustacean_model::Markdown,
     links: &mut HashMap<String, String>,
+) -> Vec<MdLine> {
+    render_markdown_with_anchors(md, links, &mut HashMap::new())
+}
+
+/// Like [`render_markdown`], but also records the index of the line that each heading is on,
+/// keyed by the heading's anchor.
+pub fn render_markdown_with_anchors(
+    md: avid_rustacean_model::Markdown,
+    links: &mut HashMap<String, String>,
+    anchors: &mut HashMap<String, usize>,
 ) -> Vec<MdLine> {
     let mut lines = vec![MdLine::Plain(Line::raw(""))];
+    let mut runnable = 0;
     for node in md.0.into_iter() {
         match node {
             MdNode::Paragraph(nodes) => {
@@ -214,7 +298,20 @@ pub fn render_markdown(
                 lines.push(MdLine::Plain(Line::raw("\n")));
             }
             MdNode::Code(code) => {
-                lines.extend(render_code(code).into_iter());
+                let playground = code.info.is_rust().then(|| playground_link(&code));
+                lines.extend(render_code(code));
+                if let Some(link) = playground {
+                    // Links are hydrated based on their text, so each needs to be unique
+                    runnable += 1;
+                    let text = format!("Run #{runnable}");
+                    links.insert(text.clone(), link);
+                    let bg = GruvboxColor::dark_3();
+                    let line = Line::from(vec![
+                        Span::styled(" ▶ ", GruvboxColor::teal().full_style(bg)),
+                        Span::styled(text, GruvboxColor::teal().full_style(bg).to_hydrate()),
+                    ]);
+                    lines.push(MdLine::Code(line, bg));
+                }
                 lines.push(MdLine::Plain(Line::raw("\n")));
             }
             MdNode::BlockQuote(block) => lines.push(MdLine::Plain(Line::styled(
@@ -222,6 +319,7 @@ pub fn render_markdown(
                 GruvboxColor::orange().full_style(GruvboxColor::dark_3()),
             ))),
             MdNode::Heading(text) => {
+                anchors.insert(slugify(&text), lines.len());
                 let line = Line::styled(
                     format!("<----- {text} ----->"),
                     GruvboxColor::yellow().fg_style(),
@@ -283,24 +381,84 @@ fn render_paragraph(nodes: Vec<MdNode>, links: &mut HashMap<String, String>) ->
 }
 
 fn render_code(code: ParsedCode) -> Vec<MdLine> {
-    let mut digest = Vec::new();
-    let mut spans = Vec::with_capacity(code.0.len());
-    for (txt, (fg, _)) in code.0 {
+    let ParsedCode { info, tokens, .. } = code;
+
+    // Split the tokens into lines
+    let mut lines = vec![Vec::new()];
+    for (txt, class) in tokens {
         let mut iter = txt.split('\n').map(ToOwned::to_owned);
-        if let Some(span) = iter.next() {
-            spans.push(Span::styled(span, fg.full_style(GruvboxColor::dark_3())))
+        if let Some(token) = iter.next() {
+            lines.last_mut().unwrap().push((token, class));
         }
-        for line in iter {
-            digest.push(MdLine::Code(Line::from(std::mem::take(&mut spans))));
-            spans.push(Span::styled(line, fg.full_style(GruvboxColor::dark_3())))
+        for token in iter {
+            lines.push(vec![(token, class)]);
         }
     }
-    if !spans.is_empty() {
-        digest.push(MdLine::Code(Line::from(std::mem::take(&mut spans))));
-    }
-    digest
+
+    let last_line = info.line_start + lines.len().saturating_sub(1);
+    let num_width = last_line.to_string().len();
+    let gutter_style = GruvboxColor::light_1().full_style(GruvboxColor::dark_2());
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, mut tokens)| {
+            let line_num = info.line_start + i;
+            let bg = if info.is_highlighted(i + 1) {
+                GruvboxColor::dark_4()
+            } else {
+                GruvboxColor::dark_3()
+            };
+            let mut spans = Vec::with_capacity(tokens.len() + 2);
+            if info.line_numbers {
+                spans.push(Span::styled(
+                    format!(" {line_num:>num_width$} "),
+                    gutter_style,
+                ));
+            }
+            if info.is_diff() {
+                // The first character of each line of a diff marks what happened to it
+                let marker = tokens
+                    .first_mut()
+                    .and_then(|(txt, _)| {
+                        let marker = txt.chars().next().filter(|c| matches!(c, '+' | '-'))?;
+                        txt.remove(0);
+                        Some(marker)
+                    })
+                    .unwrap_or(' ');
+                let style = match marker {
+                    '+' => GruvboxColor::green().full_style(GruvboxColor::dark_2()),
+                    '-' => GruvboxColor::red().full_style(GruvboxColor::dark_2()),
+                    _ => gutter_style,
+                };
+                spans.push(Span::styled(format!("{marker} "), style.bold()));
+            }
+            spans.extend(
+                tokens
+                    .into_iter()
+                    .map(|(txt, class)| Span::styled(txt, class.code_style(bg))),
+            );
+            MdLine::Code(Line::from(spans), bg)
+        })
+        .collect()
+}
+
+/// Creates a link that opens the code in the Rust Playground. The full source, including hidden
+/// lines, is used.
+fn playground_link(code: &ParsedCode) -> String {
+    let edition = code
+        .info
+        .attributes
+        .iter()
+        .find_map(|attr| attr.strip_prefix("edition"))
+        .unwrap_or("2021");
+    format!(
+        "https://play.rust-lang.org/?version=stable&mode=debug&edition={edition}&code={}",
+        url_escape::encode_component(&code.source)
+    )
 }
 
+/// Whether the screen is narrow enough for the mobile layout. Outside of the browser (e.g. when
+/// the builder prerenders pages), the desktop layout is always used.
 pub fn is_mobile() -> bool {
-    get_raw_screen_size().0 < 550
+    cfg!(target_arch = "wasm32") && get_raw_screen_size().0 < 550
 }
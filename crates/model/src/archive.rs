@@ -0,0 +1,138 @@
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::Post;
+
+/// Every post, grouped by the year and month that it was published in. Everything is sorted
+/// newest first.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Archive {
+    pub years: Vec<ArchiveYear>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveYear {
+    pub year: i32,
+    /// The number of posts from the year.
+    pub count: usize,
+    pub months: Vec<ArchiveMonth>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveMonth {
+    /// The month, from 1 to 12.
+    pub month: u32,
+    /// The name of the month, e.g. `February`.
+    pub name: String,
+    pub posts: Vec<ArchivePost>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivePost {
+    pub title: String,
+    pub real_name: String,
+    pub create_on: String,
+}
+
+impl Archive {
+    /// Groups the posts by when they were published. Posts without a valid date (which the
+    /// builder rejects anyway) are left out.
+    pub fn new<'a>(posts: impl IntoIterator<Item = &'a Post>) -> Self {
+        let mut posts: Vec<_> = posts
+            .into_iter()
+            .filter_map(|post| {
+                // The date might have a time after it
+                let date = post.summary.create_on.get(..10)?;
+                let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+                Some((date, post))
+            })
+            .collect();
+        posts.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        let mut years: Vec<ArchiveYear> = Vec::new();
+        for (date, post) in posts {
+            if years.last().is_none_or(|year| year.year != date.year()) {
+                years.push(ArchiveYear {
+                    year: date.year(),
+                    count: 0,
+                    months: Vec::new(),
+                });
+            }
+            let year = years.last_mut().unwrap();
+            year.count += 1;
+            if year
+                .months
+                .last()
+                .is_none_or(|month| month.month != date.month())
+            {
+                year.months.push(ArchiveMonth {
+                    month: date.month(),
+                    name: date.format("%B").to_string(),
+                    posts: Vec::new(),
+                });
+            }
+            year.months.last_mut().unwrap().posts.push(ArchivePost {
+                title: post.summary.title.clone(),
+                real_name: post.summary.real_name.clone(),
+                create_on: post.summary.create_on.clone(),
+            });
+        }
+        Self { years }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Post, PostSummary};
+
+    use super::Archive;
+
+    #[test]
+    fn grouped_by_year_and_month() {
+        let post = |name: &str, date: &str| Post {
+            summary: PostSummary {
+                title: name.to_uppercase(),
+                real_name: name.to_owned(),
+                create_on: date.to_owned(),
+                ..Default::default()
+            },
+            body: Default::default(),
+        };
+        let posts = [
+            post("a", "2023-12-17"),
+            post("b", "2024-02-28"),
+            post("c", "2024-02-03T10:00:00"),
+            post("d", "2024-05-01"),
+            post("e", "someday"),
+        ];
+        let archive = Archive::new(&posts);
+        let summary: Vec<_> = archive
+            .years
+            .iter()
+            .map(|year| {
+                let months: Vec<_> = year
+                    .months
+                    .iter()
+                    .map(|month| {
+                        let names: Vec<_> =
+                            month.posts.iter().map(|p| p.real_name.as_str()).collect();
+                        (month.name.as_str(), names)
+                    })
+                    .collect();
+                (year.year, year.count, months)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    2024,
+                    3,
+                    vec![("May", vec!["d"]), ("February", vec!["b", "c"])]
+                ),
+                (2023, 1, vec![("December", vec!["a"])]),
+            ]
+        );
+        assert_eq!(archive.years[0].months[1].month, 2);
+    }
+}
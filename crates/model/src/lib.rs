@@ -13,36 +13,103 @@
     clippy::all
 )]
 
-use std::error::Error;
+use std::{collections::BTreeMap, convert::Infallible, error::Error, fmt::Display, str::FromStr};
 
 pub use chrono::Utc;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
+mod archive;
 mod home;
 mod post;
+mod search;
+mod site;
 
+pub use archive::*;
 pub use home::*;
 pub use post::*;
+pub use search::*;
+pub use site::*;
 
-pub fn split_markdown(file: &str) -> (String, String) {
+/// Splits a markdown file into its TOML front matter and its body. The front matter is everything
+/// between the `+++` line at the start of the file and the next `+++` line.
+pub fn split_markdown(file: &str) -> Result<(String, String), MdParseError> {
     let mut lines = file.lines();
 
     // This should be the start of the metadata
-    assert_eq!(lines.next(), Some("+++"));
+    if lines.next() != Some("+++") {
+        return Err(MdParseError::new(
+            Some(1),
+            "expected the file to start with `+++`",
+        ));
+    }
 
     // The metadata is everything between the two lines that only contain '+++'
-    let metadata = lines
-        .by_ref()
-        .take_while(|line| *line != "+++")
-        .format("\n")
-        .to_string();
+    let mut metadata = Vec::new();
+    loop {
+        match lines.next() {
+            Some("+++") => break,
+            Some(line) => metadata.push(line),
+            None => {
+                return Err(MdParseError::new(
+                    Some(1),
+                    "the front matter is never closed with `+++`",
+                ))
+            }
+        }
+    }
 
     // Everything else should be markdown
     let md = lines.format("\n").to_string();
-    (metadata, md)
+    Ok((metadata.join("\n"), md))
+}
+
+/// Turns text into a URL-friendly slug, e.g. the anchor of a heading. Like Zola, letters and
+/// digits are lowercased and kept while everything else is collapsed into single dashes.
+pub fn slugify(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .join("-")
+}
+
+/// The canonical slug of a post, which is used in its routes and for the name of its JSON. Posts
+/// are named after their files, but names also come from URLs, so percent-encoding is decoded
+/// before the name is slugified (e.g. `My%20Post` and `My-Post.md`'s stem are both `my-post`).
+pub fn post_slug(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    slugify(&String::from_utf8_lossy(&decoded))
+}
+
+/// The scheme and host of a URL, e.g. `https://example.com` for `https://example.com/blog/`.
+pub fn origin(url: &str) -> &str {
+    let start = url.find("://").map_or(0, |i| i + "://".len());
+    match url[start..].find('/') {
+        Some(i) => &url[..start + i],
+        None => url,
+    }
 }
 
+/// Maps the old names of posts (from the `aliases` in their front matter) to their slugs.
+pub type Aliases = BTreeMap<String, String>;
+
 /// The parsed representation of markdown pages. The parsing occurs on the backend when it receives
 /// a new project, blog post, or any updates to existing pages. Colorizing the markdown is task of
 /// the frontend with the exception of syntax highlighting code blocks. Other formatting, such as
@@ -50,8 +117,77 @@ pub fn split_markdown(file: &str) -> (String, String) {
 #[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize, Hash)]
 pub struct Markdown(pub Vec<MdNode>);
 
+impl Markdown {
+    /// Returns the text and URL of every link in the document.
+    pub fn links(&self) -> Vec<(&str, &str)> {
+        fn inner<'a>(acc: &mut Vec<(&'a str, &'a str)>, nodes: &'a [MdNode]) {
+            for node in nodes {
+                match node {
+                    MdNode::Link(text, url) => acc.push((text, url)),
+                    MdNode::Paragraph(nodes) | MdNode::List(nodes) => inner(acc, nodes),
+                    _ => {}
+                }
+            }
+        }
+        let mut digest = Vec::new();
+        inner(&mut digest, &self.0);
+        digest
+    }
+
+    /// Calls the given function with the URL of every link in the document, allowing it to be
+    /// rewritten.
+    pub fn visit_links_mut<F: FnMut(&mut String)>(&mut self, mut f: F) {
+        fn inner<F: FnMut(&mut String)>(f: &mut F, nodes: &mut [MdNode]) {
+            for node in nodes {
+                match node {
+                    MdNode::Link(_, url) => f(url),
+                    MdNode::Paragraph(nodes) | MdNode::List(nodes) => inner(f, nodes),
+                    _ => {}
+                }
+            }
+        }
+        inner(&mut f, &mut self.0);
+    }
+
+    /// Returns the text of every heading in the document.
+    pub fn headings(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().filter_map(|node| match node {
+            MdNode::Heading(text) => Some(text.as_str()),
+            _ => None,
+        })
+    }
+}
+
 pub type MdError = Box<dyn Error>;
 
+/// An error from parsing a markdown document, with the line (one-indexed, relative to the start
+/// of the document) that caused it when it is known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MdParseError {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl MdParseError {
+    pub fn new(line: Option<usize>, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for MdParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {line}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl Error for MdParseError {}
+
 /// The supported markdown nodes.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub enum MdNode {
@@ -69,9 +205,153 @@ pub enum MdNode {
     Break,
 }
 
-/// Parsed and syntax highlights Rust code.
+/// Parsed and syntax highlights code. Each piece of text is tagged with the semantic class of the
+/// token it came from rather than a concrete color, so the frontend is free to pick the colors
+/// that fit its current theme.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct ParsedCode {
+    pub info: CodeInfo,
+    pub tokens: Vec<(String, TokenClass)>,
+    /// The full source of the code block. For Rust code, this includes any lines that were hidden
+    /// from the displayed tokens via rustdoc's `# ` prefix.
+    pub source: String,
+}
+
+/// The options given in the info string of a fenced code block, e.g.
+/// ```` ```rust,linenos,hl_lines=3-5 ````. These follow Zola's conventions.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
-pub struct ParsedCode(pub Vec<(String, (GruvboxColor, GruvboxColor))>);
+pub struct CodeInfo {
+    pub lang: Option<String>,
+    /// Whether to draw a gutter with line numbers (`linenos`).
+    pub line_numbers: bool,
+    /// The number of the first line (`linenostart=N`).
+    pub line_start: usize,
+    /// The inclusive, one-indexed ranges of lines that should be highlighted (`hl_lines=1 3-5`).
+    pub highlighted: Vec<(usize, usize)>,
+    /// All other attributes, such as `ignore` or `no_run`.
+    pub attributes: Vec<String>,
+}
+
+impl CodeInfo {
+    /// Diff blocks get `+`/`-` gutters instead of being highlighted.
+    pub fn is_diff(&self) -> bool {
+        matches!(self.lang.as_deref(), Some("diff" | "patch"))
+    }
+
+    /// Code blocks without a language are assumed to be Rust, like in rustdoc.
+    pub fn is_rust(&self) -> bool {
+        matches!(self.lang.as_deref(), None | Some("rust" | "rs"))
+    }
+
+    /// Checks if the given (one-indexed) line should be highlighted.
+    pub fn is_highlighted(&self, line: usize) -> bool {
+        self.highlighted
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&line))
+    }
+
+    pub fn has_attribute(&self, attr: &str) -> bool {
+        self.attributes.iter().any(|a| a == attr)
+    }
+}
+
+impl Default for CodeInfo {
+    fn default() -> Self {
+        Self {
+            lang: None,
+            line_numbers: false,
+            line_start: 1,
+            highlighted: Vec::new(),
+            attributes: Vec::new(),
+        }
+    }
+}
+
+/// The attributes that rustdoc uses to control how a code block is tested.
+pub const RUSTDOC_ATTRIBUTES: &[&str] = &[
+    "ignore",
+    "no_run",
+    "compile_fail",
+    "should_panic",
+    "test_harness",
+];
+
+impl FromStr for CodeInfo {
+    type Err = Infallible;
+
+    /// Parses an info string. Options can be separated by commas and/or whitespace. The first
+    /// option is the language unless it is a `key=value` pair.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn parse_range(s: &str) -> Option<(usize, usize)> {
+            match s.split_once('-') {
+                Some((start, end)) => Some((start.parse().ok()?, end.parse().ok()?)),
+                None => s.parse().ok().map(|line| (line, line)),
+            }
+        }
+
+        let mut digest = Self::default();
+        let mut words = s
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|w| !w.is_empty())
+            .peekable();
+        // Like rustdoc, a code block can start with an attribute instead of a language
+        let is_attribute = |w: &str| RUSTDOC_ATTRIBUTES.contains(&w) || w.starts_with("edition");
+        if let Some(lang) = words.next_if(|w| !w.contains('=') && !is_attribute(w)) {
+            digest.lang = Some(lang.to_owned());
+        }
+        while let Some(word) = words.next() {
+            match word.split_once('=') {
+                Some(("hl_lines", ranges)) => {
+                    digest.highlighted.extend(parse_range(ranges));
+                    // Zola separates ranges with spaces, so the following words might be ranges
+                    while let Some(range) = words.peek().and_then(|w| parse_range(w)) {
+                        digest.highlighted.push(range);
+                        words.next();
+                    }
+                }
+                Some(("linenostart", start)) => {
+                    digest.line_start = start.parse().unwrap_or(1);
+                }
+                None if word == "linenos" => digest.line_numbers = true,
+                _ => digest.attributes.push(word.to_owned()),
+            }
+        }
+        Ok(digest)
+    }
+}
+
+/// The semantic classes that highlighted code tokens are sorted into.
+#[derive(
+    Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize, Hash,
+)]
+pub enum TokenClass {
+    #[default]
+    Plain,
+    Keyword,
+    Type,
+    Function,
+    Macro,
+    Lifetime,
+    String,
+    Number,
+    Constant,
+    Comment,
+    Attribute,
+    Operator,
+    Punctuation,
+}
+
+/// The colors used to highlight each class of code token. These are created by quantizing a
+/// syntax highlighting theme onto the palette. Classes that are missing from the theme are left
+/// to the frontend's defaults.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize, Hash)]
+pub struct CodeTheme(pub BTreeMap<TokenClass, GruvboxColor>);
+
+impl CodeTheme {
+    pub fn get(&self, class: TokenClass) -> Option<GruvboxColor> {
+        self.0.get(&class).copied()
+    }
+}
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Hash)]
 pub enum GruvboxColor {
@@ -178,6 +458,46 @@ impl GruvboxColor {
         Self::Accent(GruvboxAccent::Pink)
     }
 
+    /// Every color in the palette.
+    pub const ALL: [Self; 16] = [
+        Self::dark_1(),
+        Self::dark_2(),
+        Self::dark_3(),
+        Self::dark_4(),
+        Self::light_1(),
+        Self::light_2(),
+        Self::light_3(),
+        Self::light_4(),
+        Self::red(),
+        Self::burnt_orange(),
+        Self::orange(),
+        Self::yellow(),
+        Self::green(),
+        Self::teal(),
+        Self::blue(),
+        Self::pink(),
+    ];
+
+    /// Finds the color in the palette that is closest to the given RGB color. This allows any
+    /// color (i.e. from an arbitrary syntax highlighting theme) to be displayed.
+    pub fn nearest(r: u8, g: u8, b: u8) -> Self {
+        let dist = |color: &Self| {
+            let (r2, g2, b2) = color.rgb();
+            [(r, r2), (g, g2), (b, b2)]
+                .into_iter()
+                .map(|(x, y)| (x.abs_diff(y) as u32).pow(2))
+                .sum::<u32>()
+        };
+        Self::ALL.into_iter().min_by_key(dist).unwrap()
+    }
+
+    /// Returns the red, green, and blue components of the color.
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        let hex = &self.hex_str()[1..];
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+        (channel(0), channel(2), channel(4))
+    }
+
     pub const fn hex_str(&self) -> &'static str {
         match self {
             GruvboxColor::Neutral(GruvboxNeutral::Dark(Shade::Darkest)) => "#1d2021",
@@ -225,5 +545,72 @@ pub const BASE_F_HEX: &str = "#d3869b";
 #[cfg(feature = "server")]
 mod server;
 
-#[cfg(feature = "server")]
-pub use server::*;
+#[cfg(test)]
+mod tests {
+    use crate::{origin, post_slug, slugify, CodeInfo, GruvboxColor};
+
+    #[test]
+    fn slugs() {
+        assert_eq!(slugify("TL;DR"), "tl-dr");
+        assert_eq!(slugify("  Hello, World!  "), "hello-world");
+        assert_eq!(
+            slugify("RFFP-0-on-the-rust-learning-curve"),
+            "rffp-0-on-the-rust-learning-curve"
+        );
+        assert_eq!(
+            post_slug("RFFP-0-on-the-rust-learning-curve"),
+            "rffp-0-on-the-rust-learning-curve"
+        );
+        assert_eq!(post_slug("about%20this%20blog"), "about-this-blog");
+        assert_eq!(post_slug("100%"), "100");
+        assert_eq!(post_slug("caf%C3%A9"), "café");
+    }
+
+    #[test]
+    fn origins() {
+        assert_eq!(origin("https://example.com/blog/"), "https://example.com");
+        assert_eq!(origin("https://example.com"), "https://example.com");
+    }
+
+    #[test]
+    fn code_info_parse() {
+        let info: CodeInfo = "rust,linenos,hl_lines=3-5".parse().unwrap();
+        assert_eq!(info.lang.as_deref(), Some("rust"));
+        assert!(info.line_numbers);
+        assert_eq!(info.highlighted, vec![(3, 5)]);
+        assert!(info.is_highlighted(4));
+        assert!(!info.is_highlighted(6));
+
+        let info: CodeInfo = "rust hl_lines=1 3-4 linenostart=10 no_run".parse().unwrap();
+        assert_eq!(info.highlighted, vec![(1, 1), (3, 4)]);
+        assert_eq!(info.line_start, 10);
+        assert!(!info.line_numbers);
+        assert!(info.has_attribute("no_run"));
+
+        let info: CodeInfo = "no_run,edition2018".parse().unwrap();
+        assert!(info.is_rust());
+        assert_eq!(info.attributes, vec!["no_run", "edition2018"]);
+
+        let info: CodeInfo = "diff".parse().unwrap();
+        assert!(info.is_diff());
+        assert_eq!("".parse::<CodeInfo>().unwrap(), CodeInfo::default());
+    }
+
+    #[test]
+    fn nearest_color() {
+        for color in GruvboxColor::ALL {
+            let (r, g, b) = color.rgb();
+            assert_eq!(GruvboxColor::nearest(r, g, b), color);
+        }
+        // Off-palette colors, including those with single-digit channels
+        assert_eq!(
+            GruvboxColor::nearest(0x0e, 0x0e, 0x0e),
+            GruvboxColor::dark_1()
+        );
+        assert_eq!(GruvboxColor::nearest(0xff, 0x00, 0x00), GruvboxColor::red());
+        assert_eq!(
+            GruvboxColor::nearest(0x60, 0x58, 0x50),
+            GruvboxColor::dark_4()
+        );
+    }
+}
@@ -10,6 +10,15 @@ pub struct Post {
     pub body: Markdown,
 }
 
+/// One page of the blog index. Pages are numbered from 1, and the posts on them are newest first.
+#[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Clone, Default)]
+pub struct PostPage {
+    pub page: usize,
+    /// The number of pages in the index.
+    pub pages: usize,
+    pub posts: Vec<PostSummary>,
+}
+
 /// A container the summary of a post
 #[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Clone, Default)]
 pub struct PostSummary {
@@ -18,4 +27,9 @@ pub struct PostSummary {
     pub summary: Markdown,
     pub create_on: String,
     pub last_edit: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The names that the post used to have, which still lead to it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
 }
@@ -0,0 +1,402 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{slugify, MdNode, Post};
+
+/// How much a match counts for, depending on where it was found.
+const TITLE_WEIGHT: u32 = 10;
+const HEADING_WEIGHT: u32 = 5;
+const CODE_WEIGHT: u32 = 2;
+const BODY_WEIGHT: u32 = 1;
+
+/// Words that are too common to be worth indexing.
+const STOP_WORDS: &[&str] = &[
+    "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have", "if", "in",
+    "into", "is", "it", "its", "of", "on", "or", "so", "that", "the", "their", "then", "there",
+    "these", "this", "to", "was", "we", "were", "will", "with",
+];
+
+/// The number of characters of a section that are shown around a match.
+const SNIPPET_LEN: usize = 120;
+
+/// An inverted index of every post, used for full-text search. Each post is split into sections
+/// at its headings so that results can link straight to the matching heading.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchIndex {
+    pub sections: Vec<Section>,
+    /// Maps each term to the sections that contain it, along with a weighted count of how often
+    /// it appears there.
+    pub terms: BTreeMap<String, Vec<(u32, u32)>>,
+}
+
+/// A section of a post, which runs from one heading to the next.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Section {
+    /// The `real_name` of the post.
+    pub post: String,
+    /// The title of the post.
+    pub title: String,
+    /// The heading that starts the section. The first section of a post has no heading.
+    pub heading: Option<String>,
+    /// The plain text of the section, used for snippets.
+    pub text: String,
+}
+
+impl Section {
+    /// The anchor of the section's heading.
+    pub fn anchor(&self) -> Option<String> {
+        self.heading.as_deref().map(slugify)
+    }
+}
+
+/// A section that matched a search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit<'a> {
+    pub section: &'a Section,
+    pub score: f32,
+    /// The part of the section around the first match, split into pieces that are either part of
+    /// a match (`true`) or not.
+    pub snippet: Vec<(String, bool)>,
+}
+
+impl SearchIndex {
+    /// Indexes the titles, headings, text, and code identifiers of every post.
+    pub fn new<'a>(posts: impl IntoIterator<Item = &'a Post>) -> Self {
+        let mut builder = IndexBuilder::default();
+        for post in posts {
+            builder.start(post, None);
+            builder.add(&post.summary.title, TITLE_WEIGHT, false);
+            for node in &post.body.0 {
+                match node {
+                    MdNode::Heading(heading) => {
+                        builder.start(post, Some(heading.clone()));
+                        builder.add(heading, HEADING_WEIGHT, false);
+                    }
+                    node => builder.add_node(node),
+                }
+            }
+        }
+        builder.finish()
+    }
+
+    /// Finds the sections that contain every term of the query, best matches first. Each term
+    /// also matches words that start with it, so results appear while a word is being typed, but
+    /// whole words count for more.
+    pub fn search(&self, query: &str) -> Vec<SearchHit<'_>> {
+        let query: Vec<String> = tokenize(query).collect();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let total = self.sections.len() as f32;
+        let mut scores: Option<HashMap<u32, f32>> = None;
+        for term in &query {
+            let mut term_scores: HashMap<u32, f32> = HashMap::new();
+            let matches = self
+                .terms
+                .range(term.clone()..)
+                .take_while(|(word, _)| word.starts_with(term.as_str()));
+            for (word, postings) in matches {
+                let idf = (1.0 + total / postings.len() as f32).ln();
+                let exact = if word == term { 1.0 } else { 0.5 };
+                for (section, count) in postings {
+                    *term_scores.entry(*section).or_default() += *count as f32 * idf * exact;
+                }
+            }
+            scores = Some(match scores {
+                None => term_scores,
+                Some(scores) => scores
+                    .into_iter()
+                    .filter_map(|(section, score)| {
+                        term_scores.get(&section).map(|s| (section, score + s))
+                    })
+                    .collect(),
+            });
+        }
+        let mut hits: Vec<_> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(section, score)| {
+                let section = self.sections.get(section as usize)?;
+                Some(SearchHit {
+                    section,
+                    score,
+                    snippet: snippet(&section.text, &query),
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.section.post.cmp(&b.section.post))
+        });
+        hits
+    }
+}
+
+#[derive(Default)]
+struct IndexBuilder {
+    sections: Vec<Section>,
+    terms: BTreeMap<String, BTreeMap<u32, u32>>,
+}
+
+impl IndexBuilder {
+    fn start(&mut self, post: &Post, heading: Option<String>) {
+        self.sections.push(Section {
+            post: post.summary.real_name.clone(),
+            title: post.summary.title.clone(),
+            heading,
+            text: String::new(),
+        });
+    }
+
+    /// Adds the terms from some text to the current section. Code also adds each part of
+    /// identifiers like `snake_case`.
+    fn add(&mut self, text: &str, weight: u32, is_code: bool) {
+        let section = self.sections.len() as u32 - 1;
+        for word in tokenize(text) {
+            if is_code && word.contains('_') {
+                for part in word.split('_').filter(|part| part.chars().count() > 1) {
+                    *self
+                        .terms
+                        .entry(part.to_owned())
+                        .or_default()
+                        .entry(section)
+                        .or_default() += weight;
+                }
+            }
+            *self
+                .terms
+                .entry(word)
+                .or_default()
+                .entry(section)
+                .or_default() += weight;
+        }
+    }
+
+    /// Adds text to the current section's snippet text as well as its terms.
+    fn add_text(&mut self, text: &str, weight: u32, is_code: bool) {
+        self.add(text, weight, is_code);
+        let section = self.sections.last_mut().unwrap();
+        for c in text.chars() {
+            if !c.is_whitespace() {
+                section.text.push(c);
+            } else if !section.text.is_empty() && !section.text.ends_with(' ') {
+                section.text.push(' ');
+            }
+        }
+    }
+
+    /// Separates blocks of text, like paragraphs, in the current section's snippet text.
+    fn end_block(&mut self) {
+        let section = self.sections.last_mut().unwrap();
+        if !section.text.is_empty() && !section.text.ends_with(' ') {
+            section.text.push(' ');
+        }
+    }
+
+    fn add_node(&mut self, node: &MdNode) {
+        match node {
+            MdNode::Paragraph(nodes) | MdNode::List(nodes) => {
+                nodes.iter().for_each(|node| self.add_node(node));
+                self.end_block();
+            }
+            MdNode::Code(code) => {
+                let code: String = code.tokens.iter().map(|(text, _)| text.as_str()).collect();
+                self.add_text(&code, CODE_WEIGHT, true);
+                self.end_block();
+            }
+            MdNode::InlineCode(code) => self.add_text(code, CODE_WEIGHT, true),
+            MdNode::BlockQuote(text) => {
+                self.add_text(text, BODY_WEIGHT, false);
+                self.end_block();
+            }
+            MdNode::Emphasis(text)
+            | MdNode::Strong(text)
+            | MdNode::Link(text, _)
+            | MdNode::Heading(text)
+            | MdNode::Text(text) => self.add_text(text, BODY_WEIGHT, false),
+            MdNode::ThematicBreak | MdNode::Break => self.end_block(),
+        }
+    }
+
+    fn finish(self) -> SearchIndex {
+        SearchIndex {
+            sections: self
+                .sections
+                .into_iter()
+                .map(|mut section| {
+                    section.text.truncate(section.text.trim_end().len());
+                    section
+                })
+                .collect(),
+            terms: self
+                .terms
+                .into_iter()
+                .map(|(term, postings)| (term, postings.into_iter().collect()))
+                .collect(),
+        }
+    }
+}
+
+/// Splits text into lowercase words. Underscores are part of words so that identifiers stay
+/// whole. Single characters and stop words are skipped.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    words(text)
+        .map(|(_, word)| word.to_lowercase())
+        .filter(|word| word.chars().count() > 1 && !STOP_WORDS.contains(&word.as_str()))
+}
+
+/// Splits text into words along with where each word starts.
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .map(move |word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+}
+
+/// Cuts out the part of the text around the first word that matches the query and marks every
+/// matching word in it.
+fn snippet(text: &str, query: &[String]) -> Vec<(String, bool)> {
+    let is_match = |word: &str| {
+        let word = word.to_lowercase();
+        query.iter().any(|term| word.starts_with(term.as_str()))
+    };
+    let first = words(text)
+        .find(|(_, word)| is_match(word))
+        .map_or(0, |(i, _)| i);
+    // Start a little before the first match, at the start of a word
+    let start = text[..first]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_LEN / 3)
+        .map_or(0, |(i, _)| i);
+    let start = match text[..first].get(start..).and_then(|t| t.find(' ')) {
+        Some(space) if start > 0 => start + space + 1,
+        _ => start,
+    };
+    let end = text[start..]
+        .char_indices()
+        .nth(SNIPPET_LEN)
+        .map_or(text.len(), |(i, _)| start + i);
+    let window = &text[start..end];
+
+    let mut digest = Vec::new();
+    if start > 0 {
+        digest.push(("…".to_owned(), false));
+    }
+    let mut last = 0;
+    for (i, word) in words(window).filter(|(_, word)| is_match(word)) {
+        if i > last {
+            digest.push((window[last..i].to_owned(), false));
+        }
+        digest.push((word.to_owned(), true));
+        last = i + word.len();
+    }
+    if last < window.len() {
+        digest.push((window[last..].to_owned(), false));
+    }
+    if end < text.len() {
+        digest.push(("…".to_owned(), false));
+    }
+    // Merge neighboring unmatched pieces
+    digest
+        .into_iter()
+        .fold(Vec::new(), |mut acc, (text, matched)| {
+            match acc.last_mut() {
+                Some((prev, false)) if !matched => prev.push_str(&text),
+                _ => acc.push((text, matched)),
+            }
+            acc
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Markdown, MdNode, Post, PostSummary};
+
+    use super::{snippet, tokenize, SearchIndex};
+
+    fn post(name: &str, title: &str, body: Vec<MdNode>) -> Post {
+        Post {
+            summary: PostSummary {
+                title: title.to_owned(),
+                real_name: name.to_owned(),
+                ..Default::default()
+            },
+            body: Markdown(body),
+        }
+    }
+
+    fn text(text: &str) -> MdNode {
+        MdNode::Paragraph(vec![MdNode::Text(text.to_owned())])
+    }
+
+    #[test]
+    fn tokens() {
+        let tokens: Vec<_> = tokenize("Rust's `send_future`, is a TUI!").collect();
+        assert_eq!(tokens, ["rust", "send_future", "tui"]);
+    }
+
+    #[test]
+    fn ranked_search() {
+        let posts = [
+            post(
+                "yew",
+                "Yew and Ratatui",
+                vec![
+                    text("Rendering a terminal in the browser."),
+                    MdNode::Heading("Callbacks".to_owned()),
+                    text("Callbacks are created with the link of a component."),
+                    MdNode::InlineCode("send_future".to_owned()),
+                ],
+            ),
+            post(
+                "rffp",
+                "On the Rust learning curve",
+                vec![text("Callbacks are not the focus here.")],
+            ),
+        ];
+        let index = SearchIndex::new(&posts);
+        assert_eq!(index.sections.len(), 3);
+        assert_eq!(
+            index.sections[1].text,
+            "Callbacks are created with the link of a component. send_future"
+        );
+
+        let hits = index.search("callbacks");
+        assert_eq!(hits.len(), 2);
+        // The heading counts for more than the body
+        assert_eq!(hits[0].section.heading.as_deref(), Some("Callbacks"));
+        assert_eq!(hits[0].section.anchor().as_deref(), Some("callbacks"));
+        assert_eq!(hits[1].section.post, "rffp");
+
+        // Every term needs to match, and the last one can be partial
+        let hits = index.search("callbacks comp");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].section.post, "yew");
+
+        // Titles and parts of identifiers are searchable
+        assert_eq!(index.search("ratatui")[0].section.heading, None);
+        assert_eq!(index.search("future")[0].section.post, "yew");
+        assert!(index.search("nothing").is_empty());
+        assert!(index.search("  ").is_empty());
+    }
+
+    #[test]
+    fn snippets() {
+        let query = ["callback".to_owned()];
+        assert_eq!(
+            snippet("Callbacks are made with a link.", &query),
+            [
+                ("Callbacks".to_owned(), true),
+                (" are made with a link.".to_owned(), false)
+            ]
+        );
+        let long = format!("{} the callback {}", "word ".repeat(40), "word ".repeat(40));
+        let pieces = snippet(long.trim(), &query);
+        assert_eq!(pieces.len(), 3);
+        assert!(pieces[0].0.starts_with("…word"));
+        assert_eq!(pieces[1], ("callback".to_owned(), true));
+        assert!(pieces[2].0.ends_with('…'));
+    }
+}
@@ -1,20 +1,19 @@
-use std::str::FromStr;
+use std::{borrow::Cow, path::Path, str::FromStr, sync::OnceLock};
 
 use markdown::{mdast::Node, ParseOptions};
 use syntect::{
-    dumps::from_binary,
-    easy::HighlightLines,
-    highlighting::{Color, Style},
-    parsing::SyntaxSet,
+    easy::ScopeRangeIterator,
+    highlighting::{Color, Highlighter, Theme, ThemeSet},
+    parsing::{ParseState, Scope, ScopeStack, SyntaxSet},
 };
 
 use super::*;
 
 impl FromStr for Markdown {
-    type Err = MdError;
+    type Err = MdParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        fn process(node: Node, nodes: &mut Vec<MdNode>) -> Result<(), MdError> {
+        fn process(node: Node, nodes: &mut Vec<MdNode>) -> Result<(), MdParseError> {
             if !matches!(node, Node::Root(_)) {
                 nodes.push((&node).try_into()?);
             }
@@ -24,7 +23,8 @@ impl FromStr for Markdown {
             Ok(())
         }
 
-        let ast = markdown::to_mdast(s, &ParseOptions::gfm()).map_err(|e| e.to_string())?;
+        let ast = markdown::to_mdast(s, &ParseOptions::gfm())
+            .map_err(|e| MdParseError::new(None, e.to_string()))?;
         let mut digest = Vec::new();
         process(ast, &mut digest)?;
         Ok(Self(digest))
@@ -32,7 +32,7 @@ impl FromStr for Markdown {
 }
 
 impl TryFrom<&Node> for MdNode {
-    type Error = MdError;
+    type Error = MdParseError;
 
     fn try_from(node: &Node) -> Result<Self, Self::Error> {
         match node {
@@ -66,13 +66,36 @@ impl TryFrom<&Node> for MdNode {
                     l.url.clone(),
                 ))
             }
-            Node::Code(code) => parse_code(&code.value).map(Self::Code),
+            Node::Code(code) => {
+                let info = [code.lang.as_deref(), code.meta.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .join(" ");
+                parse_code(&code.value, info.parse().unwrap_or_else(|e| match e {}))
+                    .map(Self::Code)
+                    .map_err(|e| {
+                        MdParseError::new(line(node), format!("could not highlight code: {e}"))
+                    })
+            }
             Node::Html(_) => Ok(Self::Paragraph(Vec::new())),
-            node => Err(MdError::from(format!("Unsupported node type: {node:?}"))),
+            node => {
+                // The debug output of a node starts with the name of its type
+                let debug = format!("{node:?}");
+                let kind = debug.split(['(', ' ', '{']).next().unwrap_or_default();
+                Err(MdParseError::new(
+                    line(node),
+                    format!("unsupported markdown: {kind}"),
+                ))
+            }
         }
     }
 }
 
+/// The line that a node starts on.
+fn line(node: &Node) -> Option<usize> {
+    node.position().map(|pos| pos.start.line)
+}
+
 /// A loosely method for turning markdown nodes into strings.
 fn nodes_to_string(nodes: &[Node]) -> String {
     fn inner(acc: &mut String, nodes: &[Node]) {
@@ -89,78 +112,282 @@ fn nodes_to_string(nodes: &[Node]) -> String {
     digest
 }
 
-/// Parses a Rust code block and highlights the syntax.
-fn parse_code(code: &str) -> Result<ParsedCode, MdError> {
-    static GRUVBYTES: &[u8] = include_bytes!("../assets/gruvbox.dump");
-    let syntaxes = SyntaxSet::load_defaults_nonewlines();
-    let theme = from_binary(GRUVBYTES);
-    let mut hl = HighlightLines::new(syntaxes.find_syntax_by_name("Rust").unwrap(), &theme);
-    let mut digest = Vec::new();
-    for line in code.split_inclusive('\n') {
-        let parsed = hl.highlight_line(line, &syntaxes)?;
-        for (style, item) in parsed {
-            digest.push((item.replace('\t', "  "), convert_style(style)?));
+/// Parses a code block and sorts each token into its semantic class. Code blocks without a
+/// language are assumed to be Rust. Unknown languages are left as plain text.
+fn parse_code(code: &str, info: CodeInfo) -> Result<ParsedCode, MdError> {
+    let syntaxes = syntaxes();
+    let syntax = syntaxes
+        .find_syntax_by_token(info.lang.as_deref().unwrap_or("rust"))
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+    let mut state = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+    let mut digest: Vec<(String, TokenClass)> = Vec::new();
+    let mut push = |txt: &str, class: TokenClass| match digest.last_mut() {
+        Some((prev, prev_class)) if *prev_class == class => prev.push_str(txt),
+        _ => digest.push((txt.to_owned(), class)),
+    };
+    let mut source = Vec::new();
+    let mut first_visible = true;
+    for line in code.lines() {
+        let (line, visible) = if info.is_rust() {
+            unhide_line(line)
+        } else {
+            (Cow::Borrowed(line), true)
+        };
+        if visible && !std::mem::take(&mut first_visible) {
+            push("\n", TokenClass::Plain);
         }
+        // Hidden lines are still parsed so that the lines after them are in the right state
+        let ops = state.parse_line(&line, syntaxes)?;
+        for (range, op) in ScopeRangeIterator::new(&ops, &line) {
+            stack.apply(op)?;
+            if visible && !range.is_empty() {
+                push(&line[range].replace('\t', "  "), classify(stack.as_slice()));
+            }
+        }
+        source.push(line);
     }
-    Ok(ParsedCode(digest))
+    Ok(ParsedCode {
+        info,
+        tokens: digest,
+        source: source.join("\n"),
+    })
 }
 
-fn convert_style(style: Style) -> Result<(GruvboxColor, GruvboxColor), MdError> {
-    let fg = if let Ok(fg) = style.foreground.try_into() {
-        fg
-    } else if style.foreground
-        == (Color {
-            r: 146,
-            g: 131,
-            b: 116,
-            a: 255,
-        })
-    {
-        GruvboxColor::orange()
+/// The syntaxes used to highlight code. Loading these is slow, so they are loaded once and shared
+/// by every code block.
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_nonewlines)
+}
+
+/// Processes rustdoc's syntax for hidden lines. A line that starts with `# ` (or is just `#`) is
+/// hidden, and a leading `##` is used to escape a `#`. Returns the line as it should be compiled
+/// and whether or not it should be displayed.
+fn unhide_line(line: &str) -> (Cow<'_, str>, bool) {
+    let trimmed = line.trim_start();
+    if trimmed == "#" {
+        (Cow::Borrowed(""), false)
+    } else if let Some(hidden) = trimmed.strip_prefix("# ") {
+        (Cow::Borrowed(hidden), false)
+    } else if trimmed.starts_with("##") {
+        let indent = &line[..line.len() - trimmed.len()];
+        (Cow::Owned(format!("{indent}{}", &trimmed[1..])), true)
     } else {
-        return Err("Style error".into());
-    };
-    let Ok(bg) = style.background.try_into() else {
-        return Err("Style error".into());
-    };
-    Ok((fg, bg))
+        (Cow::Borrowed(line), true)
+    }
 }
 
-#[derive(Debug)]
-pub struct ColorError;
-
-impl TryFrom<Color> for GruvboxColor {
-    type Error = ColorError;
-
-    fn try_from(Color { r, g, b, .. }: Color) -> Result<Self, Self::Error> {
-        match format!("#{r:x}{g:x}{b:x}").as_str() {
-            BASE_0_HEX => Ok(GruvboxColor::dark_1()),
-            BASE_1_HEX => Ok(GruvboxColor::dark_2()),
-            BASE_2_HEX => Ok(GruvboxColor::dark_3()),
-            BASE_3_HEX => Ok(GruvboxColor::dark_4()),
-            BASE_4_HEX => Ok(GruvboxColor::light_1()),
-            BASE_5_HEX => Ok(GruvboxColor::light_2()),
-            BASE_6_HEX => Ok(GruvboxColor::light_3()),
-            BASE_7_HEX => Ok(GruvboxColor::light_4()),
-            BASE_8_HEX => Ok(GruvboxColor::red()),
-            BASE_9_HEX => Ok(GruvboxColor::burnt_orange()),
-            BASE_A_HEX => Ok(GruvboxColor::orange()),
-            BASE_B_HEX => Ok(GruvboxColor::yellow()),
-            BASE_C_HEX => Ok(GruvboxColor::green()),
-            BASE_D_HEX => Ok(GruvboxColor::teal()),
-            BASE_E_HEX => Ok(GruvboxColor::blue()),
-            BASE_F_HEX => Ok(GruvboxColor::pink()),
-            _ => Err(ColorError),
+/// Picks the semantic class for a token based on the stack of scopes that it is in. The innermost
+/// scope that has a meaningful class wins. Punctuation is only used as a fallback so that things
+/// like the quotes of a string or the brackets of an attribute take on the class of their parent.
+fn classify(scopes: &[Scope]) -> TokenClass {
+    let mut fallback = TokenClass::Plain;
+    for scope in scopes.iter().rev() {
+        match classify_scope(*scope) {
+            Some(TokenClass::Punctuation) => fallback = TokenClass::Punctuation,
+            Some(class) => return class,
+            None => {}
         }
     }
+    fallback
+}
+
+fn classify_scope(scope: Scope) -> Option<TokenClass> {
+    // The order of this table matters as more specific scopes need to be checked before their
+    // parents (e.g. `keyword.operator` before `keyword`).
+    const TABLE: &[(&str, TokenClass)] = &[
+        ("comment", TokenClass::Comment),
+        ("string", TokenClass::String),
+        ("constant.character", TokenClass::String),
+        ("constant.numeric", TokenClass::Number),
+        ("constant", TokenClass::Constant),
+        ("storage.modifier.lifetime", TokenClass::Lifetime),
+        ("entity.name.lifetime", TokenClass::Lifetime),
+        ("support.macro", TokenClass::Macro),
+        ("entity.name.macro", TokenClass::Macro),
+        ("meta.annotation", TokenClass::Attribute),
+        ("meta.attribute", TokenClass::Attribute),
+        ("keyword.operator", TokenClass::Operator),
+        ("keyword", TokenClass::Keyword),
+        ("storage.modifier", TokenClass::Keyword),
+        ("entity.name.function", TokenClass::Function),
+        ("support.function", TokenClass::Function),
+        ("entity.name", TokenClass::Type),
+        ("support.type", TokenClass::Type),
+        ("support.class", TokenClass::Type),
+        ("punctuation", TokenClass::Punctuation),
+    ];
+
+    // `storage.type` is used both for keywords that introduce items (`struct`, `fn`, `impl`) and
+    // for the builtin types (`u32`, `str`). The former always have a more specific scope, e.g.
+    // `storage.type.struct.rust`, while the latter are just `storage.type.rust`.
+    let storage_type = Scope::new("storage.type").unwrap();
+    if storage_type.is_prefix_of(scope) {
+        return Some(if scope.len() > 3 {
+            TokenClass::Keyword
+        } else {
+            TokenClass::Type
+        });
+    }
+
+    TABLE
+        .iter()
+        .find(|(prefix, _)| Scope::new(prefix).unwrap().is_prefix_of(scope))
+        .map(|(_, class)| *class)
+}
+
+impl CodeTheme {
+    /// Loads a `.tmTheme` file and quantizes its colors onto the palette.
+    pub fn load(path: &Path) -> Result<Self, MdError> {
+        Ok(Self::from_theme(&ThemeSet::get_theme(path)?))
+    }
+
+    /// Quantizes a syntect theme onto the palette. Each token class is looked up in the theme via
+    /// a representative stack of scopes.
+    pub fn from_theme(theme: &Theme) -> Self {
+        const SCOPES: &[(TokenClass, &str)] = &[
+            (TokenClass::Plain, "source.rust"),
+            (TokenClass::Keyword, "source.rust keyword.other.rust"),
+            (TokenClass::Type, "source.rust entity.name.type.rust"),
+            (
+                TokenClass::Function,
+                "source.rust entity.name.function.rust",
+            ),
+            (TokenClass::Macro, "source.rust support.macro.rust"),
+            (
+                TokenClass::Lifetime,
+                "source.rust storage.modifier.lifetime.rust",
+            ),
+            (TokenClass::String, "source.rust string.quoted.double.rust"),
+            (
+                TokenClass::Number,
+                "source.rust constant.numeric.integer.decimal.rust",
+            ),
+            (TokenClass::Constant, "source.rust constant.language.rust"),
+            (
+                TokenClass::Comment,
+                "source.rust comment.line.double-slash.rust",
+            ),
+            (
+                TokenClass::Attribute,
+                "source.rust meta.annotation.rust variable.annotation.rust",
+            ),
+            (TokenClass::Operator, "source.rust keyword.operator.rust"),
+            (
+                TokenClass::Punctuation,
+                "source.rust punctuation.separator.rust",
+            ),
+        ];
+
+        let highlighter = Highlighter::new(theme);
+        let colors = SCOPES
+            .iter()
+            .filter_map(|(class, scopes)| {
+                let stack = ScopeStack::from_str(scopes).ok()?;
+                let color = highlighter.style_for_stack(stack.as_slice()).foreground;
+                match class {
+                    // A fully transparent color means that the theme does not set it
+                    _ if color.a == 0 => None,
+                    // Comments are usually gray, which the palette doesn't have, so they keep the
+                    // frontend's color for them rather than becoming whatever is nearest
+                    TokenClass::Comment if is_gray(color) => None,
+                    _ => Some((*class, color.into())),
+                }
+            })
+            .collect();
+        Self(colors)
+    }
+}
+
+/// Whether a color is close to a shade of gray, i.e. its channels are all about the same.
+fn is_gray(Color { r, g, b, .. }: Color) -> bool {
+    r.max(g).max(b) - r.min(g).min(b) < 0x20
+}
+
+impl From<Color> for GruvboxColor {
+    fn from(Color { r, g, b, .. }: Color) -> Self {
+        GruvboxColor::nearest(r, g, b)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use syntect::dumps::from_binary;
+
     use super::parse_code;
+    use crate::{CodeInfo, CodeTheme, GruvboxColor, Markdown, MdNode, TokenClass};
 
     #[test]
     fn basic_code_parse() {
-        parse_code("pub struct HelloWorld;").unwrap();
+        parse_code("pub struct HelloWorld;", CodeInfo::default()).unwrap();
+    }
+
+    #[test]
+    fn code_is_classified() {
+        let code = parse_code("#[derive(Debug)]\nfn foo<'a>(x: &'a str) -> u32 {\n    // Hi\n    println!(\"{x}\");\n    42\n}", CodeInfo::default()).unwrap();
+        let class_of = |txt: &str| {
+            code.tokens
+                .iter()
+                .find(|(t, _)| t.trim() == txt)
+                .map(|(_, class)| *class)
+                .unwrap()
+        };
+        assert_eq!(class_of("#[derive(Debug)]"), TokenClass::Attribute);
+        assert_eq!(class_of("fn"), TokenClass::Keyword);
+        assert_eq!(class_of("foo"), TokenClass::Function);
+        assert_eq!(class_of("'a"), TokenClass::Lifetime);
+        assert_eq!(class_of("u32"), TokenClass::Type);
+        assert_eq!(class_of("// Hi"), TokenClass::Comment);
+        assert_eq!(class_of("println!"), TokenClass::Macro);
+        assert_eq!(class_of("42"), TokenClass::Number);
+        let text: String = code.tokens.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(text.lines().count(), 6);
+    }
+
+    #[test]
+    fn gruvbox_theme_quantizes() {
+        static GRUVBYTES: &[u8] = include_bytes!("../assets/gruvbox.dump");
+        let theme = CodeTheme::from_theme(&from_binary(GRUVBYTES));
+        assert_eq!(theme.get(TokenClass::Keyword), Some(GruvboxColor::red()));
+        assert_eq!(theme.get(TokenClass::String), Some(GruvboxColor::green()));
+        // The gruvbox comment color (#928374) is a gray, so comments are left to the frontend,
+        // which shows them in orange
+        assert_eq!(theme.get(TokenClass::Comment), None);
+    }
+
+    #[test]
+    fn code_block_info() {
+        let md: Markdown =
+            "```rust,linenos,hl_lines=2\nfn main() {\n}\n```\n\n```diff\n-a\n+b\n```"
+                .parse()
+                .unwrap();
+        let [MdNode::Code(rust), MdNode::Code(diff)] = md.0.as_slice() else {
+            panic!("{md:?}")
+        };
+        assert_eq!(rust.info.lang.as_deref(), Some("rust"));
+        assert!(rust.info.line_numbers);
+        assert!(rust.info.is_highlighted(2));
+        assert!(diff.info.is_diff());
+        let text: String = diff.tokens.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(text, "-a\n+b");
+    }
+
+    #[test]
+    fn hidden_lines() {
+        let code = "# fn main() {\nlet x = 5;\n    ## not hidden\n#\n# }";
+        let parsed = parse_code(code, CodeInfo::default()).unwrap();
+        let text: String = parsed.tokens.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(text, "let x = 5;\n    # not hidden");
+        assert_eq!(
+            parsed.source,
+            "fn main() {\nlet x = 5;\n    # not hidden\n\n}"
+        );
+
+        // Only Rust has hidden lines
+        let parsed = parse_code("# Title", "md".parse().unwrap()).unwrap();
+        assert_eq!(parsed.source, "# Title");
+        let text: String = parsed.tokens.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(text, "# Title");
     }
 }
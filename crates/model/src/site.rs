@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// The parts of the site's configuration that the frontend needs to draw its header and footer.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Clone)]
+pub struct SiteInfo {
+    pub title: String,
+    /// The URL of the whole site (i.e. the GUI), e.g. `https://avid-rustacean.dev`.
+    pub base_url: String,
+    /// The path that the TUI is served under, e.g. `/tui`.
+    pub base_path: String,
+    pub author: Option<String>,
+    /// Links that leave the TUI, which are shown in the footer.
+    pub links: Vec<MenuItem>,
+}
+
+/// A named link, like one of the menu items in Zola's `config.toml`.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Clone)]
+pub struct MenuItem {
+    pub name: String,
+    pub url: String,
+}
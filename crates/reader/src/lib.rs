@@ -0,0 +1,22 @@
+#![warn(rust_2018_idioms)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    unreachable_pub,
+    unreachable_patterns,
+    unused,
+    unused_qualifications,
+    while_true,
+    trivial_casts,
+    trivial_bounds,
+    trivial_numeric_casts,
+    unconditional_panic,
+    clippy::all
+)]
+
+//! Reads the blog in a real terminal, with the TUI's own pages. The binary draws a [`Session`]
+//! with crossterm, and the session can be driven by anything else that draws with ratatui.
+//!
+//! [`Session`]: session::Session
+
+pub mod session;
+pub mod source;
@@ -0,0 +1,120 @@
+#![warn(rust_2018_idioms)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    unreachable_pub,
+    unreachable_patterns,
+    unused,
+    unused_qualifications,
+    while_true,
+    trivial_casts,
+    trivial_bounds,
+    trivial_numeric_casts,
+    unconditional_panic,
+    clippy::all
+)]
+
+use std::{
+    io::{stdout, Stdout},
+    process::{Command, ExitCode, Stdio},
+};
+
+use avid_rustacean_reader::{
+    session::{route, Action, Session},
+    source::Source,
+};
+use clap::Parser;
+use crossterm::{
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyEventKind, MouseEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{backend::CrosstermBackend, Terminal};
+
+/// Reads the blog in the terminal, using the builder's output.
+#[derive(Debug, Parser)]
+struct Cli {
+    /// The builder's output directory, or the URL of a site's TUI (e.g.
+    /// `https://avid-rustacean.dev/tui`).
+    source: String,
+    /// The route of the page to start on (e.g. `blog/<post>`). Defaults to the home page.
+    #[arg(default_value = "")]
+    page: String,
+    /// The command that opens links that leave the TUI.
+    #[arg(long, default_value = "xdg-open")]
+    open_with: String,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let Some(page) = route(&cli.page) else {
+        eprintln!("`{}` is not a page of the TUI", cli.page);
+        return ExitCode::FAILURE;
+    };
+    let mut session = match Session::new(Source::new(&cli.source), page) {
+        Ok(session) => session,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match run(&mut session, &cli.open_with) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Takes over the terminal until the reader quits.
+fn run(session: &mut Session, open_with: &str) -> std::io::Result<()> {
+    enable_raw_mode()?;
+    execute!(stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    // A panic would otherwise leave the terminal unusable
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore();
+        hook(info)
+    }));
+    let result = Terminal::new(CrosstermBackend::new(stdout()))
+        .and_then(|mut terminal| read(&mut terminal, session, open_with));
+    restore()?;
+    result
+}
+
+fn restore() -> std::io::Result<()> {
+    disable_raw_mode()?;
+    execute!(stdout(), LeaveAlternateScreen, DisableMouseCapture)
+}
+
+fn read(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    session: &mut Session,
+    open_with: &str,
+) -> std::io::Result<()> {
+    loop {
+        terminal.draw(|frame| session.draw(frame))?;
+        let action = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => session.key(key),
+            // The mouse is reported whenever it moves, which doesn't need a redraw
+            Event::Mouse(mouse) if mouse.kind == MouseEventKind::Moved => continue,
+            Event::Mouse(mouse) => session.mouse(mouse),
+            _ => Action::Redraw,
+        };
+        match action {
+            Action::Redraw => {}
+            Action::Open(url) => {
+                let opened = Command::new(open_with)
+                    .arg(&url)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn();
+                if let Err(err) = opened {
+                    session.notify(format!("could not open {url} with `{open_with}`: {err}"));
+                }
+            }
+            Action::Quit => return Ok(()),
+        }
+    }
+}
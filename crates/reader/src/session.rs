@@ -0,0 +1,449 @@
+use avid_rustacean_frontend::{
+    app::{AppBodyProps, Link, PageData, TermApp},
+    palette::{indexed_color_rgb, set_code_theme},
+    site::set_site,
+};
+use avid_rustacean_model::{origin, CodeTheme, SiteInfo};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    Frame,
+};
+use webatui::{backend::HYDRATION, TerminalApp};
+
+use crate::source::{LoadError, Source};
+
+/// One reader of the TUI: the page that they are on, the pages that they came from, and the link
+/// that they have selected. The session doesn't know about the terminal that it is drawn in, so
+/// it can be driven by anything that draws with ratatui and has crossterm's events.
+pub struct Session {
+    source: Source,
+    site: SiteInfo,
+    theme: CodeTheme,
+    app: TermApp,
+    page: AppBodyProps,
+    /// The pages that were left for the current one, most recent last.
+    history: Vec<AppBodyProps>,
+    /// Every link on the screen and where it is, as of the last time that it was drawn.
+    links: Vec<(Rect, Link)>,
+    /// The index of the link that Enter follows.
+    selected: Option<usize>,
+    /// A message shown over the bottom of the screen until the next key press.
+    status: Option<String>,
+    /// The height of the screen, as of the last time that it was drawn.
+    height: u16,
+}
+
+/// What the terminal should do after the session handled an event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Redraw,
+    /// Open a link that leaves the TUI, and then redraw.
+    Open(String),
+    Quit,
+}
+
+/// The page at a route of the TUI (e.g. `blog/<post>`), relative to the path that it is served
+/// under.
+pub fn route(route: &str) -> Option<AppBodyProps> {
+    match route.trim_matches('/') {
+        "" => Some(AppBodyProps::Home),
+        "projects" => Some(AppBodyProps::AllProjects),
+        "blog" => Some(AppBodyProps::Blog(1)),
+        "archive" => Some(AppBodyProps::Archive),
+        route => {
+            let post = route.strip_prefix("blog/")?;
+            let (name, anchor) = match post.split_once('#') {
+                Some((name, anchor)) => (name, Some(anchor.to_owned())),
+                None => (post, None),
+            };
+            (!name.is_empty() && !name.contains('/'))
+                .then(|| AppBodyProps::Post(name.to_owned(), anchor))
+        }
+    }
+}
+
+impl Session {
+    /// Starts a session on the given page.
+    pub fn new(source: Source, page: AppBodyProps) -> Result<Self, LoadError> {
+        let site = source.site()?;
+        let theme = source.code_theme();
+        // The code theme is used when the page is loaded
+        set_site(site.clone());
+        set_code_theme(theme.clone());
+        let (page, data) = source.load(page)?;
+        Ok(Self {
+            source,
+            site,
+            theme,
+            app: app(&page, data),
+            page,
+            history: Vec::new(),
+            links: Vec::new(),
+            selected: None,
+            status: None,
+            height: 0,
+        })
+    }
+
+    /// The page that the session is on.
+    pub fn page(&self) -> &AppBodyProps {
+        &self.page
+    }
+
+    /// Shows a message over the bottom of the screen until the next key press.
+    pub fn notify(&mut self, message: impl Into<String>) {
+        self.status = Some(message.into());
+    }
+
+    /// Draws the app over the whole frame.
+    pub fn draw(&mut self, frame: &mut Frame<'_>) {
+        self.enter();
+        let area = frame.size();
+        self.height = area.height;
+        self.app.render(area, frame);
+        let buffer = frame.buffer_mut();
+        self.links = find_links(buffer)
+            .into_iter()
+            .filter_map(|(rect, text)| Some((rect, self.app.link(&text)?)))
+            .collect();
+        if let Some((rect, _)) = self.selected.and_then(|i| self.links.get(i)) {
+            buffer.set_style(*rect, Style::new().add_modifier(Modifier::REVERSED));
+        }
+        if let Some(status) = &self.status {
+            let style = Style::new().fg(Color::Indexed(0)).bg(Color::Indexed(11));
+            let y = area.bottom().saturating_sub(1);
+            buffer.set_style(Rect::new(area.x, y, area.width, 1), style);
+            buffer.set_stringn(
+                area.x + 1,
+                y,
+                status,
+                (area.width as usize).saturating_sub(1),
+                style,
+            );
+        }
+        for_terminal(buffer);
+    }
+
+    pub fn key(&mut self, key: KeyEvent) -> Action {
+        self.status = None;
+        let page = self.height.saturating_sub(TermApp::CHROME_HEIGHT).max(1) as isize;
+        let end = self.app.content_length() as isize;
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+            KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+            KeyCode::Down | KeyCode::Char('j') => self.scroll(1),
+            KeyCode::Up | KeyCode::Char('k') => self.scroll(-1),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.scroll(page),
+            KeyCode::PageUp => self.scroll(-page),
+            KeyCode::End | KeyCode::Char('G') => self.scroll(end),
+            KeyCode::Home | KeyCode::Char('g') => self.scroll(-end),
+            KeyCode::Tab => self.select(1),
+            KeyCode::BackTab => self.select(-1),
+            KeyCode::Enter => match self.selected.and_then(|i| self.links.get(i)) {
+                Some((_, link)) => self.follow(link.clone()),
+                None => Action::Redraw,
+            },
+            KeyCode::Backspace => {
+                if let Some(page) = self.history.pop() {
+                    self.go(page);
+                }
+                Action::Redraw
+            }
+            KeyCode::Char('r') => {
+                self.go(self.page.clone());
+                Action::Redraw
+            }
+            // Anything else might be for the page, like the arrows that switch pages of the index
+            code => {
+                let name = match code {
+                    KeyCode::Left => "ArrowLeft".to_owned(),
+                    KeyCode::Right => "ArrowRight".to_owned(),
+                    KeyCode::Char(c) => c.to_string(),
+                    _ => return Action::Redraw,
+                };
+                if let Some(page) = self.app.handle_key(&name) {
+                    self.open(page);
+                }
+                Action::Redraw
+            }
+        }
+    }
+
+    pub fn mouse(&mut self, mouse: MouseEvent) -> Action {
+        match mouse.kind {
+            MouseEventKind::ScrollDown => self.scroll(1),
+            MouseEventKind::ScrollUp => self.scroll(-1),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let clicked = self.links.iter().find(|(rect, _)| {
+                    rect.y == mouse.row && (rect.left()..rect.right()).contains(&mouse.column)
+                });
+                match clicked {
+                    Some((_, link)) => self.follow(link.clone()),
+                    None => Action::Redraw,
+                }
+            }
+            _ => Action::Redraw,
+        }
+    }
+
+    /// The frontend's state is kept per thread, so it is set up again before anything that uses
+    /// it, in case another session on the same thread changed it.
+    fn enter(&self) {
+        set_site(self.site.clone());
+        set_code_theme(self.theme.clone());
+    }
+
+    fn scroll(&mut self, lines: isize) -> Action {
+        // The links move, so the selection would point at the wrong one
+        self.selected = None;
+        self.app.scroll_by(lines);
+        Action::Redraw
+    }
+
+    /// Moves the selection through the links on the screen, wrapping around at either end.
+    fn select(&mut self, by: isize) -> Action {
+        let len = self.links.len() as isize;
+        if len > 0 {
+            let next = match self.selected {
+                Some(i) => (i as isize + by).rem_euclid(len),
+                None if by > 0 => 0,
+                None => len - 1,
+            };
+            self.selected = Some(next as usize);
+        }
+        Action::Redraw
+    }
+
+    fn follow(&mut self, link: Link) -> Action {
+        match link {
+            Link::Page(page) => {
+                self.open(page);
+                Action::Redraw
+            }
+            // Links within the site are relative to its root
+            Link::Url(url) if url.starts_with('/') => {
+                Action::Open(format!("{}{url}", origin(&self.site.base_url)))
+            }
+            Link::Url(url) => Action::Open(url),
+            Link::Search => {
+                self.notify("Search is only in the browser, for now");
+                Action::Redraw
+            }
+        }
+    }
+
+    /// Switches to another page, which the previous one can be gone back to from.
+    fn open(&mut self, page: AppBodyProps) {
+        let from = self.page.clone();
+        if self.go(page) {
+            self.history.push(from);
+        }
+    }
+
+    /// Switches to another page, returning whether it could be loaded. If it couldn't, the
+    /// session stays where it is.
+    fn go(&mut self, page: AppBodyProps) -> bool {
+        self.enter();
+        match self.source.load(page) {
+            Ok((page, data)) => {
+                self.app = app(&page, data);
+                self.page = page;
+                self.selected = None;
+                true
+            }
+            Err(err) => {
+                self.notify(err.to_string());
+                false
+            }
+        }
+    }
+}
+
+/// The app for a page that was just loaded, which jumps to the page's anchor if it has one.
+fn app(page: &AppBodyProps, data: PageData) -> TermApp {
+    let anchor = match page {
+        AppBodyProps::Post(_, anchor) => anchor.clone(),
+        _ => None,
+    };
+    TermApp::with_anchor(data, anchor)
+}
+
+/// Finds the spans that the frontend marked for hydration, which are its links, along with where
+/// they are.
+fn find_links(buffer: &Buffer) -> Vec<(Rect, String)> {
+    let area = buffer.area;
+    let mut digest = Vec::new();
+    for y in area.top()..area.bottom() {
+        let mut run: Option<(Rect, String, Style)> = None;
+        for x in area.left()..area.right() {
+            let cell = buffer.get(x, y);
+            let style = cell.style();
+            match &mut run {
+                Some((rect, text, run_style)) if *run_style == style => {
+                    rect.width += 1;
+                    text.push_str(cell.symbol());
+                }
+                _ => {
+                    digest.extend(run.take().map(|(rect, text, _)| (rect, text)));
+                    if cell.modifier.contains(HYDRATION) {
+                        run = Some((Rect::new(x, y, 1, 1), cell.symbol().to_owned(), style));
+                    }
+                }
+            }
+        }
+        digest.extend(run.map(|(rect, text, _)| (rect, text)));
+    }
+    digest
+}
+
+/// Makes a drawn buffer look like it does in the browser. The frontend's colors are indices into
+/// its palette, which would be the terminal's own palette here, so they are swapped for the colors
+/// that the browser shows. The modifier that marks links would make them blink, so it is removed.
+fn for_terminal(buffer: &mut Buffer) {
+    let swap = |color: Color| match color {
+        Color::Indexed(i) if i < 16 => indexed_color_rgb(i),
+        color => color,
+    };
+    for cell in &mut buffer.content {
+        cell.fg = swap(cell.fg);
+        cell.bg = swap(cell.bg);
+        cell.modifier.remove(HYDRATION);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use avid_rustacean_frontend::app::{AppBodyProps, Link};
+    use avid_rustacean_model::{Archive, Post, PostSummary, SiteInfo};
+    use crossterm::event::{KeyCode, KeyEvent};
+    use ratatui::{
+        backend::TestBackend,
+        buffer::Buffer,
+        layout::Rect,
+        style::{Color, Style},
+        Terminal,
+    };
+    use webatui::backend::HYDRATION;
+
+    use super::{find_links, for_terminal, route, Action, Session};
+    use crate::source::Source;
+
+    #[test]
+    fn links_are_followed() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("posts")).unwrap();
+        let post = Post {
+            summary: PostSummary {
+                title: "A Post".to_owned(),
+                real_name: "a-post".to_owned(),
+                create_on: "2024-01-02".to_owned(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let site = SiteInfo {
+            title: "Blog".to_owned(),
+            base_url: "https://example.com".to_owned(),
+            base_path: "/tui".to_owned(),
+            ..Default::default()
+        };
+        let write = |name: &str, json: String| std::fs::write(dir.path().join(name), json).unwrap();
+        write("site.json", serde_json::to_string(&site).unwrap());
+        write("posts/a-post.json", serde_json::to_string(&post).unwrap());
+        write(
+            "archive.json",
+            serde_json::to_string(&Archive::new([&post])).unwrap(),
+        );
+
+        let mut session =
+            Session::new(Source::Dir(dir.path().to_owned()), AppBodyProps::Archive).unwrap();
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        let mut draw = |session: &mut Session| {
+            terminal.draw(|frame| session.draw(frame)).unwrap();
+        };
+        draw(&mut session);
+        let post_link = Link::Page(AppBodyProps::Post("a-post".to_owned(), None));
+        let tabs = session
+            .links
+            .iter()
+            .position(|(_, link)| *link == post_link);
+        let press = |session: &mut Session, code| session.key(KeyEvent::from(code));
+        for _ in 0..=tabs.unwrap() {
+            press(&mut session, KeyCode::Tab);
+        }
+        assert_eq!(press(&mut session, KeyCode::Enter), Action::Redraw);
+        assert_eq!(
+            session.page(),
+            &AppBodyProps::Post("a-post".to_owned(), None)
+        );
+
+        // Links that leave the TUI are opened by the terminal
+        draw(&mut session);
+        let atom = session
+            .links
+            .iter()
+            .find(|(_, link)| matches!(link, Link::Url(_)));
+        assert!(atom.is_some());
+        let opened = session.follow(Link::Url("/tui/atom.xml".to_owned()));
+        assert_eq!(
+            opened,
+            Action::Open("https://example.com/tui/atom.xml".to_owned())
+        );
+
+        // Pages that can't be loaded leave the session where it is
+        press(&mut session, KeyCode::Backspace);
+        assert_eq!(session.page(), &AppBodyProps::Archive);
+        session.open(AppBodyProps::Home);
+        assert_eq!(session.page(), &AppBodyProps::Archive);
+        assert!(session.status.as_ref().unwrap().contains("home.json"));
+        assert_eq!(press(&mut session, KeyCode::Char('q')), Action::Quit);
+    }
+
+    #[test]
+    fn routes() {
+        assert_eq!(route(""), Some(AppBodyProps::Home));
+        assert_eq!(route("/blog/"), Some(AppBodyProps::Blog(1)));
+        assert_eq!(route("archive"), Some(AppBodyProps::Archive));
+        assert_eq!(
+            route("blog/some-post#intro"),
+            Some(AppBodyProps::Post(
+                "some-post".to_owned(),
+                Some("intro".to_owned())
+            ))
+        );
+        assert_eq!(route("blog/a/b"), None);
+        assert_eq!(route("nowhere"), None);
+    }
+
+    #[test]
+    fn links_are_found_by_their_style() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 12, 2));
+        let link = Style::new().add_modifier(HYDRATION);
+        buffer.set_string(0, 0, "Home", link);
+        buffer.set_string(4, 0, " | ", Style::new());
+        buffer.set_string(7, 0, "Blog", link);
+        buffer.set_string(2, 1, "Archive", link.fg(Color::Indexed(8)));
+        assert_eq!(
+            find_links(&buffer),
+            vec![
+                (Rect::new(0, 0, 4, 1), "Home".to_owned()),
+                (Rect::new(7, 0, 4, 1), "Blog".to_owned()),
+                (Rect::new(2, 1, 7, 1), "Archive".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn buffers_look_like_the_browser() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 1, 1));
+        let style = Style::new().fg(Color::Indexed(0)).add_modifier(HYDRATION);
+        buffer.set_string(0, 0, "x", style);
+        for_terminal(&mut buffer);
+        assert_eq!(buffer.get(0, 0).fg, Color::Rgb(0x1d, 0x20, 0x21));
+        assert_eq!(buffer.get(0, 0).bg, Color::Reset);
+        assert!(buffer.get(0, 0).modifier.is_empty());
+    }
+}
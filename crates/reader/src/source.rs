@@ -0,0 +1,158 @@
+use std::{fmt::Display, path::PathBuf};
+
+use avid_rustacean_frontend::app::{AppBodyProps, PageData};
+use avid_rustacean_model::{post_slug, Aliases, CodeTheme, SiteInfo};
+use serde::de::DeserializeOwned;
+
+/// Where the builder's output is read from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// The builder's output directory (e.g. `crates/assets`), which is laid out like the TUI of
+    /// the deployed site.
+    Dir(PathBuf),
+    /// The TUI of a deployed (or locally served) site, e.g. `https://avid-rustacean.dev/tui`.
+    Http(String),
+}
+
+/// A file that could not be read or understood.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadError {
+    pub file: String,
+    pub message: String,
+}
+
+impl Source {
+    /// A source for either a URL or a path.
+    pub fn new(source: &str) -> Self {
+        if source.starts_with("http://") || source.starts_with("https://") {
+            Self::Http(source.trim_end_matches('/').to_owned())
+        } else {
+            Self::Dir(source.into())
+        }
+    }
+
+    /// The site's config.
+    pub fn site(&self) -> Result<SiteInfo, LoadError> {
+        self.json("site.json")
+    }
+
+    /// The theme used to color highlighted code, which the builder only writes when it is given
+    /// one.
+    pub fn code_theme(&self) -> CodeTheme {
+        self.json("code-theme.json").unwrap_or_default()
+    }
+
+    /// Loads the data of a page, returning the page that the data is for. This is only different
+    /// from the given page when a post was found by one of its aliases.
+    pub fn load(&self, page: AppBodyProps) -> Result<(AppBodyProps, PageData), LoadError> {
+        let data = match &page {
+            AppBodyProps::Home => PageData::Home(self.json("home.json")?),
+            AppBodyProps::AllProjects => PageData::AllProjects(self.json("projects.json")?),
+            AppBodyProps::Blog(page) => PageData::Blog(self.json(&format!("posts-{page}.json"))?),
+            AppBodyProps::Archive => PageData::Archive(self.json("archive.json")?),
+            AppBodyProps::Post(name, anchor) => {
+                let name = post_slug(name);
+                match self.json(&format!("posts/{name}.json")) {
+                    Ok(post) => PageData::Post(post),
+                    // The post might have been renamed, in which case its old name is an alias
+                    Err(err) => {
+                        let aliases: Aliases = self.json("aliases.json").unwrap_or_default();
+                        return match aliases.get(&name) {
+                            Some(name) => {
+                                self.load(AppBodyProps::Post(name.clone(), anchor.clone()))
+                            }
+                            None => Err(err),
+                        };
+                    }
+                }
+            }
+        };
+        Ok((page, data))
+    }
+
+    /// Reads and parses one of the TUI's files, named like it is in the deployed site (e.g.
+    /// `posts/<post>.json`).
+    fn json<T: DeserializeOwned>(&self, file: &str) -> Result<T, LoadError> {
+        let error = |message: String| LoadError {
+            file: file.to_owned(),
+            message,
+        };
+        let text = match self {
+            Self::Dir(dir) => {
+                std::fs::read_to_string(dir.join(file)).map_err(|err| error(err.to_string()))?
+            }
+            Self::Http(url) => ureq::get(&format!("{url}/{file}"))
+                .call()
+                .and_then(|resp| resp.into_body().read_to_string())
+                .map_err(|err| error(err.to_string()))?,
+        };
+        serde_json::from_str(&text).map_err(|err| error(err.to_string()))
+    }
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "could not load `{}`: {}", self.file, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use avid_rustacean_frontend::app::{AppBodyProps, PageData};
+    use avid_rustacean_model::{Post, PostSummary};
+
+    use super::Source;
+
+    #[test]
+    fn sources() {
+        assert_eq!(
+            Source::new("https://avid-rustacean.dev/tui/"),
+            Source::Http("https://avid-rustacean.dev/tui".to_owned())
+        );
+        assert_eq!(
+            Source::new("crates/assets"),
+            Source::Dir(PathBuf::from("crates/assets"))
+        );
+    }
+
+    #[test]
+    fn posts_are_found_by_their_aliases() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("posts")).unwrap();
+        let post = Post {
+            summary: PostSummary {
+                title: "New Name".to_owned(),
+                real_name: "new-name".to_owned(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        std::fs::write(
+            dir.path().join("posts/new-name.json"),
+            serde_json::to_string(&post).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("aliases.json"),
+            r#"{"old-name":"new-name"}"#,
+        )
+        .unwrap();
+        let source = Source::Dir(dir.path().to_owned());
+
+        let old = AppBodyProps::Post("Old-Name".to_owned(), Some("intro".to_owned()));
+        let (page, data) = source.load(old).unwrap();
+        assert_eq!(
+            page,
+            AppBodyProps::Post("new-name".to_owned(), Some("intro".to_owned()))
+        );
+        assert!(matches!(data, PageData::Post(post) if post.summary.title == "New Name"));
+
+        let err = source
+            .load(AppBodyProps::Post("missing".to_owned(), None))
+            .unwrap_err();
+        assert_eq!(err.file, "posts/missing.json");
+        assert!(source.load(AppBodyProps::Home).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+use std::{iter::Peekable, str::Chars};
+
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+
+/// Turns what a client's terminal sent into crossterm's events. Crossterm can only read the
+/// terminal that it runs in, so the keys (and the escape sequences of the keys that aren't
+/// characters) are parsed here instead. Anything that isn't understood is dropped.
+pub(crate) fn parse(input: &[u8]) -> Vec<Event> {
+    let input = String::from_utf8_lossy(input);
+    let mut chars = input.chars().peekable();
+    let mut events = Vec::new();
+    while let Some(c) = chars.next() {
+        let event = match c {
+            '\x1b' => match chars.peek() {
+                Some('[') => {
+                    chars.next();
+                    csi(&mut chars)
+                }
+                Some('O') => {
+                    chars.next();
+                    chars.next().and_then(ss3)
+                }
+                _ => Some(key(KeyCode::Esc)),
+            },
+            '\r' | '\n' => Some(key(KeyCode::Enter)),
+            '\t' => Some(key(KeyCode::Tab)),
+            '\x7f' | '\x08' => Some(key(KeyCode::Backspace)),
+            // Ctrl and a letter is sent as the letter's place in the alphabet
+            '\x01'..='\x1a' => {
+                let letter = char::from(b'a' + c as u8 - 1);
+                Some(Event::Key(KeyEvent::new(
+                    KeyCode::Char(letter),
+                    KeyModifiers::CONTROL,
+                )))
+            }
+            c if c.is_control() => None,
+            c => Some(key(KeyCode::Char(c))),
+        };
+        events.extend(event);
+    }
+    events
+}
+
+fn key(code: KeyCode) -> Event {
+    Event::Key(KeyEvent::from(code))
+}
+
+/// A control sequence, after its `ESC [`.
+fn csi(chars: &mut Peekable<Chars<'_>>) -> Option<Event> {
+    let mut params = String::new();
+    let last = loop {
+        match chars.next()? {
+            c @ ('0'..='9' | ';' | '<') => params.push(c),
+            c => break c,
+        }
+    };
+    if let Some(params) = params.strip_prefix('<') {
+        return mouse(params, last);
+    }
+    let code = match (last, params.as_str()) {
+        ('A', _) => KeyCode::Up,
+        ('B', _) => KeyCode::Down,
+        ('C', _) => KeyCode::Right,
+        ('D', _) => KeyCode::Left,
+        ('H', _) | ('~', "1" | "7") => KeyCode::Home,
+        ('F', _) | ('~', "4" | "8") => KeyCode::End,
+        ('Z', _) => {
+            return Some(Event::Key(KeyEvent::new(
+                KeyCode::BackTab,
+                KeyModifiers::SHIFT,
+            )))
+        }
+        ('~', "2") => KeyCode::Insert,
+        ('~', "3") => KeyCode::Delete,
+        ('~', "5") => KeyCode::PageUp,
+        ('~', "6") => KeyCode::PageDown,
+        _ => return None,
+    };
+    Some(key(code))
+}
+
+/// The keys that some terminals send as `ESC O` and a letter.
+fn ss3(c: char) -> Option<Event> {
+    let code = match c {
+        'A' => KeyCode::Up,
+        'B' => KeyCode::Down,
+        'C' => KeyCode::Right,
+        'D' => KeyCode::Left,
+        'H' => KeyCode::Home,
+        'F' => KeyCode::End,
+        _ => return None,
+    };
+    Some(key(code))
+}
+
+/// A mouse event in the SGR encoding (`ESC [ < button ; column ; row M`), where `M` is a press
+/// and `m` is a release. Only clicks and the wheel are used by the reader.
+fn mouse(params: &str, last: char) -> Option<Event> {
+    let mut params = params.split(';').map(|param| param.parse::<u16>().ok());
+    let (button, column, row) = (params.next()??, params.next()??, params.next()??);
+    let kind = match (button, last) {
+        (0, 'M') => MouseEventKind::Down(MouseButton::Left),
+        (0, 'm') => MouseEventKind::Up(MouseButton::Left),
+        (64, 'M') => MouseEventKind::ScrollUp,
+        (65, 'M') => MouseEventKind::ScrollDown,
+        _ => return None,
+    };
+    Some(Event::Mouse(MouseEvent {
+        kind,
+        // The terminal counts from 1
+        column: column.saturating_sub(1),
+        row: row.saturating_sub(1),
+        modifiers: KeyModifiers::NONE,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{
+        Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    };
+
+    use super::parse;
+
+    fn keys(input: &str) -> Vec<KeyCode> {
+        parse(input.as_bytes())
+            .into_iter()
+            .map(|event| match event {
+                Event::Key(key) => key.code,
+                event => panic!("{event:?} is not a key"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keys_are_parsed() {
+        assert_eq!(
+            keys("jq é"),
+            [
+                KeyCode::Char('j'),
+                KeyCode::Char('q'),
+                KeyCode::Char(' '),
+                KeyCode::Char('é')
+            ]
+        );
+        assert_eq!(
+            keys("\r\t\x7f\x1b"),
+            [
+                KeyCode::Enter,
+                KeyCode::Tab,
+                KeyCode::Backspace,
+                KeyCode::Esc
+            ]
+        );
+        assert_eq!(
+            keys("\x1b[A\x1bOB\x1b[1;5C\x1b[5~\x1b[6~\x1b[H\x1b[4~\x1b[Z"),
+            [
+                KeyCode::Up,
+                KeyCode::Down,
+                KeyCode::Right,
+                KeyCode::PageUp,
+                KeyCode::PageDown,
+                KeyCode::Home,
+                KeyCode::End,
+                KeyCode::BackTab
+            ]
+        );
+        assert_eq!(
+            parse(b"\x03"),
+            [Event::Key(KeyEvent::new(
+                KeyCode::Char('c'),
+                KeyModifiers::CONTROL
+            ))]
+        );
+        // Sequences that aren't known (or are cut off) are dropped
+        assert_eq!(keys("\x1b[99~k\x1b["), [KeyCode::Char('k')]);
+    }
+
+    #[test]
+    fn mice_are_parsed() {
+        let mouse = |kind, column, row| {
+            Event::Mouse(MouseEvent {
+                kind,
+                column,
+                row,
+                modifiers: KeyModifiers::NONE,
+            })
+        };
+        assert_eq!(
+            parse(b"\x1b[<0;10;3M\x1b[<0;10;3m\x1b[<65;1;1M\x1b[<35;4;4M"),
+            [
+                mouse(MouseEventKind::Down(MouseButton::Left), 9, 2),
+                mouse(MouseEventKind::Up(MouseButton::Left), 9, 2),
+                mouse(MouseEventKind::ScrollDown, 0, 0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,102 @@
+#![warn(rust_2018_idioms)]
+#![deny(
+    rustdoc::broken_intra_doc_links,
+    unreachable_pub,
+    unreachable_patterns,
+    unused,
+    unused_qualifications,
+    while_true,
+    trivial_casts,
+    trivial_bounds,
+    trivial_numeric_casts,
+    unconditional_panic,
+    clippy::all
+)]
+
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+    time::Duration,
+};
+
+use clap::Parser;
+use russh::{
+    keys::{load_secret_key, ssh_key::LineEnding, Algorithm, PrivateKey},
+    server::{Config, Server},
+};
+use server::Blog;
+use tokio::net::TcpListener;
+
+mod input;
+mod screen;
+mod server;
+
+/// Serves the blog's TUI over SSH, using the builder's output.
+#[derive(Debug, Parser)]
+struct Cli {
+    /// The builder's output directory.
+    #[arg(long, default_value = "crates/assets")]
+    out: PathBuf,
+    /// The address to listen on.
+    #[arg(long, default_value = "127.0.0.1:2222")]
+    listen: SocketAddr,
+    /// The server's private key, which is generated if it doesn't exist yet.
+    #[arg(long, default_value = "target/ssh-host-key")]
+    key: PathBuf,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let key = match host_key(&cli.key) {
+        Ok(key) => key,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let config = Config {
+        keys: vec![key],
+        inactivity_timeout: Some(Duration::from_secs(60 * 60)),
+        auth_rejection_time: Duration::from_secs(1),
+        auth_rejection_time_initial: Some(Duration::ZERO),
+        nodelay: true,
+        ..Default::default()
+    };
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let served = runtime.block_on(async {
+        let listener = TcpListener::bind(cli.listen).await?;
+        println!(
+            "Serving the blog on `ssh -p {} {}`",
+            cli.listen.port(),
+            cli.listen.ip()
+        );
+        Blog::new(cli.out)
+            .run_on_socket(Arc::new(config), &listener)
+            .await
+    });
+    match served {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("could not serve on {}: {err}", cli.listen);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Reads the server's key, or makes one so that clients see the same key every time.
+fn host_key(path: &Path) -> Result<PrivateKey, String> {
+    let error = |err: &dyn std::fmt::Display| format!("{}: {err}", path.display());
+    if path.exists() {
+        return load_secret_key(path, None).map_err(|err| error(&err));
+    }
+    let key =
+        PrivateKey::random(&mut rand::rng(), Algorithm::Ed25519).map_err(|err| error(&err))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|err| error(&err))?;
+    }
+    key.write_openssh_file(path, LineEnding::LF)
+        .map_err(|err| error(&err))?;
+    Ok(key)
+}
@@ -0,0 +1,370 @@
+use std::{collections::HashMap, net::SocketAddr, path::PathBuf};
+
+use avid_rustacean_reader::{
+    session::{route, Action, Session},
+    source::Source,
+};
+use crossterm::{
+    cursor::Show,
+    event::{DisableMouseCapture, EnableMouseCapture, Event, MouseEventKind},
+    queue,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::Terminal;
+use russh::{
+    keys::PublicKey,
+    server::{self, Auth, ChannelOpenHandle, Msg},
+    Channel, ChannelId, Pty,
+};
+
+use crate::{
+    input::parse,
+    screen::{Output, Screen},
+};
+
+/// Serves the TUI to everyone who connects, from the builder's output.
+#[derive(Debug, Clone)]
+pub(crate) struct Blog {
+    out: PathBuf,
+}
+
+/// One connection. Each of its channels (usually there is just the one) is a reader with their
+/// own terminal.
+pub(crate) struct Connection {
+    out: PathBuf,
+    clients: HashMap<ChannelId, Client>,
+}
+
+/// A terminal that asked for the TUI, and the session that is drawn in it once it has started.
+struct Client {
+    terminal: Terminal<Screen>,
+    output: Output,
+    session: Option<Session>,
+}
+
+impl Blog {
+    pub(crate) fn new(out: PathBuf) -> Self {
+        Self { out }
+    }
+}
+
+impl server::Server for Blog {
+    type Handler = Connection;
+
+    fn new_client(&mut self, _: Option<SocketAddr>) -> Connection {
+        Connection {
+            out: self.out.clone(),
+            clients: HashMap::new(),
+        }
+    }
+
+    fn handle_session_error(&mut self, err: russh::Error) {
+        eprintln!("{err}");
+    }
+}
+
+impl Connection {
+    /// Starts the TUI on the given page, if the channel has a terminal to draw it in.
+    fn start(
+        &mut self,
+        channel: ChannelId,
+        page: &str,
+        session: &mut server::Session,
+    ) -> Result<(), russh::Error> {
+        session.channel_success(channel)?;
+        let Some(client) = self.clients.get_mut(&channel) else {
+            return fail(
+                channel,
+                "The blog needs a terminal (try `ssh -t`).",
+                session,
+            );
+        };
+        let Some(page) = route(page) else {
+            return fail(
+                channel,
+                &format!("`{page}` is not a page of the blog."),
+                session,
+            );
+        };
+        let reader = match Session::new(Source::Dir(self.out.clone()), page) {
+            Ok(reader) => reader,
+            Err(err) => return fail(channel, &err.to_string(), session),
+        };
+        client.session = Some(reader);
+        queue!(client.output, EnterAlternateScreen, EnableMouseCapture)?;
+        client.draw(channel, session)
+    }
+}
+
+impl server::Handler for Connection {
+    type Error = russh::Error;
+
+    // Anyone can read the blog
+    async fn auth_none(&mut self, _: &str) -> Result<Auth, Self::Error> {
+        Ok(Auth::Accept)
+    }
+
+    async fn auth_publickey(&mut self, _: &str, _: &PublicKey) -> Result<Auth, Self::Error> {
+        Ok(Auth::Accept)
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _: Channel<Msg>,
+        reply: ChannelOpenHandle,
+        _: &mut server::Session,
+    ) -> Result<(), Self::Error> {
+        reply.accept().await;
+        Ok(())
+    }
+
+    async fn pty_request(
+        &mut self,
+        channel: ChannelId,
+        _: &str,
+        width: u32,
+        height: u32,
+        _: u32,
+        _: u32,
+        _: &[(Pty, u32)],
+        session: &mut server::Session,
+    ) -> Result<(), Self::Error> {
+        let output = Output::default();
+        let screen = Screen::new(output.clone(), clamp(width), clamp(height));
+        let client = Client {
+            terminal: Terminal::new(screen)?,
+            output,
+            session: None,
+        };
+        self.clients.insert(channel, client);
+        session.channel_success(channel)
+    }
+
+    /// `ssh blog.example` starts on the home page.
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut server::Session,
+    ) -> Result<(), Self::Error> {
+        self.start(channel, "", session)
+    }
+
+    /// `ssh -t blog.example blog/<post>` starts on another page.
+    async fn exec_request(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut server::Session,
+    ) -> Result<(), Self::Error> {
+        let page = String::from_utf8_lossy(data);
+        self.start(channel, page.trim(), session)
+    }
+
+    async fn data(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut server::Session,
+    ) -> Result<(), Self::Error> {
+        let Some(client) = self.clients.get_mut(&channel) else {
+            return Ok(());
+        };
+        let Some(reader) = &mut client.session else {
+            return Ok(());
+        };
+        for event in parse(data) {
+            let action = match event {
+                Event::Key(key) => reader.key(key),
+                Event::Mouse(mouse) if mouse.kind == MouseEventKind::Moved => continue,
+                Event::Mouse(mouse) => reader.mouse(mouse),
+                _ => continue,
+            };
+            match action {
+                Action::Redraw => {}
+                // The link can only be opened on the reader's side, so they are shown where it
+                // leads instead
+                Action::Open(url) => reader.notify(url),
+                Action::Quit => {
+                    queue!(
+                        client.output,
+                        Show,
+                        DisableMouseCapture,
+                        LeaveAlternateScreen
+                    )?;
+                    session.data(channel, client.output.take())?;
+                    self.clients.remove(&channel);
+                    return exit(channel, 0, session);
+                }
+            }
+        }
+        client.draw(channel, session)
+    }
+
+    async fn window_change_request(
+        &mut self,
+        channel: ChannelId,
+        width: u32,
+        height: u32,
+        _: u32,
+        _: u32,
+        session: &mut server::Session,
+    ) -> Result<(), Self::Error> {
+        match self.clients.get_mut(&channel) {
+            Some(client) => {
+                client
+                    .terminal
+                    .backend_mut()
+                    .resize(clamp(width), clamp(height));
+                client.draw(channel, session)
+            }
+            None => Ok(()),
+        }
+    }
+
+    async fn channel_close(
+        &mut self,
+        channel: ChannelId,
+        _: &mut server::Session,
+    ) -> Result<(), Self::Error> {
+        self.clients.remove(&channel);
+        Ok(())
+    }
+}
+
+impl Client {
+    /// Draws the session, if it has started, and sends what changed to the client.
+    fn draw(
+        &mut self,
+        channel: ChannelId,
+        session: &mut server::Session,
+    ) -> Result<(), russh::Error> {
+        if let Some(reader) = &mut self.session {
+            self.terminal.draw(|frame| reader.draw(frame))?;
+        }
+        session.data(channel, self.output.take())
+    }
+}
+
+/// Tells the client why the TUI couldn't start, and ends the channel.
+fn fail(
+    channel: ChannelId,
+    message: &str,
+    session: &mut server::Session,
+) -> Result<(), russh::Error> {
+    session.data(channel, format!("{message}\r\n"))?;
+    exit(channel, 1, session)
+}
+
+fn exit(
+    channel: ChannelId,
+    status: u32,
+    session: &mut server::Session,
+) -> Result<(), russh::Error> {
+    session.exit_status_request(channel, status)?;
+    session.eof(channel)?;
+    session.close(channel)
+}
+
+/// A size sent by the client, which might be more than any terminal can be.
+fn clamp(size: u32) -> u16 {
+    size.try_into().unwrap_or(u16::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use avid_rustacean_model::{HomePage, SiteInfo};
+    use russh::{
+        client,
+        keys::{Algorithm, PrivateKey, PublicKeyOrCertificate},
+        server::{Config, Server},
+        ChannelMsg,
+    };
+    use tokio::net::TcpListener;
+
+    use super::Blog;
+
+    struct Trusting;
+
+    impl client::Handler for Trusting {
+        type Error = russh::Error;
+
+        async fn check_server_key(
+            &mut self,
+            _: &PublicKeyOrCertificate,
+        ) -> Result<bool, Self::Error> {
+            Ok(true)
+        }
+    }
+
+    /// Reads what the server sends until it has sent something that matches, returning all of it.
+    async fn read_until(
+        channel: &mut russh::Channel<client::Msg>,
+        done: impl Fn(&str, Option<u32>) -> bool,
+    ) -> String {
+        let mut read = String::new();
+        let mut status = None;
+        while !done(&read, status) {
+            match channel.wait().await {
+                Some(ChannelMsg::Data { data }) => read.push_str(&String::from_utf8_lossy(&data)),
+                Some(ChannelMsg::ExitStatus { exit_status }) => status = Some(exit_status),
+                Some(_) => {}
+                None => panic!("the channel closed after {read:?}"),
+            }
+        }
+        read
+    }
+
+    #[tokio::test]
+    async fn the_blog_is_served() {
+        let dir = tempfile::tempdir().unwrap();
+        let site = SiteInfo {
+            title: "Served Over SSH".to_owned(),
+            ..Default::default()
+        };
+        let write = |name: &str, json: String| std::fs::write(dir.path().join(name), json).unwrap();
+        write("site.json", serde_json::to_string(&site).unwrap());
+        write(
+            "home.json",
+            serde_json::to_string(&HomePage::default()).unwrap(),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let config = Config {
+            keys: vec![PrivateKey::random(&mut rand::rng(), Algorithm::Ed25519).unwrap()],
+            ..Default::default()
+        };
+        let mut blog = Blog::new(dir.path().to_owned());
+        tokio::spawn(async move { blog.run_on_socket(Arc::new(config), &listener).await });
+
+        let mut handle = client::connect(Arc::new(client::Config::default()), addr, Trusting)
+            .await
+            .unwrap();
+        assert!(handle.authenticate_none("reader").await.unwrap().success());
+
+        // Without a terminal, there's nothing to draw in
+        let mut channel = handle.channel_open_session().await.unwrap();
+        channel.request_shell(true).await.unwrap();
+        let read = read_until(&mut channel, |_, status| status.is_some()).await;
+        assert!(read.contains("needs a terminal"));
+
+        let mut channel = handle.channel_open_session().await.unwrap();
+        channel
+            .request_pty(true, "xterm", 100, 30, 0, 0, &[])
+            .await
+            .unwrap();
+        channel.request_shell(true).await.unwrap();
+        read_until(&mut channel, |read, _| read.contains("Served Over SSH")).await;
+
+        // Resizing the client's terminal redraws all of it
+        channel.window_change(60, 20, 0, 0).await.unwrap();
+        read_until(&mut channel, |read, _| read.contains("\x1b[2J")).await;
+
+        channel.data(&b"q"[..]).await.unwrap();
+        let read = read_until(&mut channel, |_, status| status == Some(0)).await;
+        // The client's screen is put back the way that it was
+        assert!(read.contains("\x1b[?1049l"));
+    }
+}
@@ -2,75 +2,12 @@
 
 set -e
 
-# Assembles all of the assets for the web TUI and puts them in the static directory for zola
-
-# First, check to see if the static directory exists and create the structure if not
-if [ -d "static" ]; then
-				rm -r static
-fi
-
-mkdir static
-mkdir static/tui
-mkdir static/tui/projects
-mkdir static/tui/blog
-mkdir static/tui/posts
-
-# Adds CNAME to static because this project uses a custom domain name
-cp CNAME static/
-
-pushd crates
-
-# Compile the web TUI frontend
-pushd frontend
-trunk build --dist ../assets --filehash=false --release=true --no-sri=false index.html
-popd
-
-# Compress the WASM binary and move it into statics
-pushd assets
-
-tar -czf avid-rustacean-frontend_bg.wasm.gz avid-rustacean-frontend_bg.wasm
-
-cp index.html ../../static/tui/
-cp avid-rustacean-frontend.js ../../static/
-cp avid-rustacean-frontend_bg.wasm ../../static/
-cp avid-rustacean-frontend_bg.wasm.gz ../../static/
-
-popd
-
-# Copy data from content into asserts to be parsed into JSON
-popd
-pushd content
-
-cp ./*.md ../crates/assets/
-cp pages/projects.md ../crates/assets/
-cp pages/about/index.md ../crates/assets/home.md
-
-popd
-pushd crates
-
-# Compile and run then builder to generate the JSON
-pushd builder
-cargo run
-popd
-
-# Move generated JSON into statics
-pushd assets
-
-mv badge.json ../../static/
-mv home.json ../../static/tui/
-
-mv projects.json ../../static/tui/
-cp index.html ../../static/tui/projects/
-
-mv posts.json ../../static/tui/
-cp index.html ../../static/tui/blog/
-
-for file in $(ls ./*.json)
-do
-				mv $file ../../static/tui/posts/
-				DIR_NAME=$(basename -- "$file" .json)
-				mkdir ../../static/tui/blog/$DIR_NAME
-				cp index.html ../../static/tui/blog/$DIR_NAME/
-done
-
-popd
+# Assembles all of the assets for the web TUI and puts them in the static directory for zola. The
+# builder does all of the work (see `crates/builder/README.md`); this just runs it from anywhere.
+#
+# A custom syntax highlighting theme can be used for code blocks by setting CODE_THEME to the path
+# of a `.tmTheme` file.
+CODE_THEME=${CODE_THEME:+$(realpath "$CODE_THEME")}
+
+cd "$(dirname "$0")/.."
+cargo run --release --package avid-rustacean-builder -- assemble ${CODE_THEME:+--theme "$CODE_THEME"}